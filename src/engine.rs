@@ -0,0 +1,1141 @@
+use rand::prelude::*;
+
+use std::collections::VecDeque;
+
+use crate::entities::{
+    ability::Ability,
+    effect::{Effect, Tick},
+    item::Item,
+    monster::Monster,
+    player::Player,
+    Cave, CaveDifficulty, Inventory, Level, Rarity, RawInventory, RoomKind, World,
+};
+
+/// Number of clues the player gets about each cave before choosing one
+pub const CLUES_PER_CAVE: usize = 2;
+
+/// XP awarded for surviving a cave, once the player has gone past its entrance
+pub const MIN_CAVE_XP: u64 = 500;
+/// XP awarded for each level of a slain monster
+pub const MONSTER_XP_PER_LEVEL: u64 = 20;
+/// How many times the usual XP a slain boss is worth
+pub const BOSS_XP_MULTIPLIER: u64 = 5;
+/// Chance to flee a monster that drastically outclasses the player
+pub const ESCAPE_CHANCE: f64 = 0.20;
+/// Chance for the player to flee a monster of their own level
+pub const FLEE_CHANCE: f64 = 0.5;
+/// Added to the chance to flee for every level the player has over the
+/// monster, or taken away for every level under it
+pub const FLEE_CHANCE_PER_LEVEL: f64 = 0.02;
+/// Fraction of max HP healed for every level gained
+pub const LEVEL_UP_HEAL: f64 = 0.5;
+
+pub struct CaveReward {
+    pub xp: u64,
+    pub gold: u64,
+    pub loot: RawInventory,
+    /// What each defeated monster dropped, kept apart from the cave's loot
+    pub drops: Vec<(Monster, RawInventory)>,
+}
+
+pub enum CaveResult {
+    Survived { reward: CaveReward },
+    Died,
+}
+
+pub enum RoomResult {
+    /// The player is still standing, though monsters they escaped from may
+    /// still be in the room
+    Survived,
+    Died,
+}
+
+/// The player's way through a cave: where they are and what they've earned.
+pub struct Expedition {
+    pub room: usize,
+    /// Whether the player has gone past the entrance, so that turning straight
+    /// back earns nothing
+    pub explored: bool,
+    pub reward: CaveReward,
+}
+
+/// What the player does on their turn in combat.
+pub enum Action {
+    Attack,
+    /// Brace for the monster's next attack, taking less damage from it
+    Defend,
+    /// Drink a potion or use another item, by id
+    Use {
+        item: String,
+    },
+    /// Try to get away from the monster, see [`flee_chance`]
+    Flee,
+}
+
+/// Something the player makes out about a cave from its entrance. Clues can be
+/// misread, see [`Player::clue_accuracy`].
+pub enum Clue {
+    /// Nothing stirs inside
+    Silence,
+    /// Noises that sound about as dangerous as the cave
+    Sounds {
+        difficulty: CaveDifficulty,
+    },
+    Smell {
+        monster: Monster,
+    },
+    /// Tracks of one monster, or of a whole group of them
+    Tracks {
+        monster: Monster,
+        many: bool,
+    },
+    Glimpse {
+        monster: Monster,
+    },
+}
+
+/// Something that happened in the game, emitted as it happens.
+pub enum GameEvent<'a> {
+    /// The player sizes up a cave before choosing which to enter
+    CaveScouted {
+        name: &'a str,
+    },
+    Clue {
+        clue: &'a Clue,
+    },
+    CaveEntered {
+        name: &'a str,
+    },
+    RoomEntered {
+        kind: RoomKind,
+        /// Whether the player has been in the room before
+        visited: bool,
+    },
+    NoEnemies,
+    Encounter {
+        monster: &'a Monster,
+        difficult: bool,
+    },
+    /// A boss was encountered, instead of [`GameEvent::Encounter`]
+    BossIntro {
+        monster: &'a Monster,
+    },
+    /// A boss was hurt badly enough to enter its next phase
+    BossPhase {
+        monster: &'a Monster,
+        phase: &'a str,
+    },
+    /// Who gets to attack first
+    Initiative {
+        monster: &'a Monster,
+        player_first: bool,
+    },
+    Retreat {
+        monster: &'a Monster,
+    },
+    /// The player hit a monster without killing it
+    Attack {
+        monster: &'a Monster,
+        weapon: Option<&'a Item>,
+        damage: u64,
+    },
+    Attacked {
+        monster: &'a Monster,
+        damage: u64,
+    },
+    PotionUsed {
+        potion: &'a Item,
+    },
+    /// The player chose to use an item in combat
+    ItemUsed {
+        item: &'a Item,
+        cured: Vec<Effect>,
+        granted: Vec<Effect>,
+    },
+    /// The item the player chose can't be used, which still costs their turn.
+    /// `None` if the id they chose isn't an item at all.
+    ItemUseFailed {
+        item: Option<&'a Item>,
+    },
+    Defend,
+    FleeFailed {
+        monster: &'a Monster,
+    },
+    /// A monster with an ability was encountered
+    AbilityRevealed {
+        monster: &'a Monster,
+        ability: &'a str,
+    },
+    /// A ranged monster fires before the fight starts
+    RangedShot {
+        monster: &'a Monster,
+        ability: &'a str,
+    },
+    Dodged {
+        monster: &'a Monster,
+        ability: &'a str,
+    },
+    Regenerated {
+        monster: &'a Monster,
+        ability: &'a str,
+        hp: u64,
+    },
+    Summoned {
+        monster: &'a Monster,
+        ability: &'a str,
+        summoned: &'a Monster,
+    },
+    /// A monster ran off, and is still in the room
+    Fled {
+        monster: &'a Monster,
+        ability: &'a str,
+    },
+    /// The player's class let them slip out of the way of a monster's attack
+    Evaded {
+        monster: &'a Monster,
+        ability: &'a str,
+    },
+    /// The player's class healed them at the start of their turn
+    Recovered {
+        ability: &'a str,
+        hp: u64,
+    },
+    /// A monster's hit left the player with an effect
+    Afflicted {
+        monster: &'a Monster,
+        effect: Effect,
+    },
+    EffectTicked {
+        effect: Effect,
+        damage: u64,
+    },
+    EffectEnded {
+        effect: Effect,
+    },
+    /// The player survived the first blow from a monster
+    Taunt {
+        monster: &'a Monster,
+    },
+    Slain {
+        monster: &'a Monster,
+        first_strike: bool,
+    },
+    /// Follows [`GameEvent::Slain`] for bosses
+    BossDefeated {
+        monster: &'a Monster,
+    },
+    Reward {
+        xp: u64,
+        gold: u64,
+        loot: Vec<(&'a Item, u32)>,
+        /// Items dropped by each monster
+        drops: Vec<(&'a Monster, Vec<(&'a Item, u32)>)>,
+    },
+    /// Looted gear was put on, for a slot that was empty
+    Equipped {
+        item: &'a Item,
+    },
+    /// Looted gear is better than what the player has on, but stays in the
+    /// inventory
+    BetterGear {
+        item: &'a Item,
+        equipped: &'a Item,
+    },
+    LevelUp {
+        /// Number of levels gained at once
+        gained: u64,
+        level: u64,
+        hp: u64,
+    },
+    Died,
+}
+
+/// Consumes the events emitted by the engine, e.g. to draw them to a terminal,
+/// and asks the player what to do in combat.
+pub trait Renderer {
+    fn render(&mut self, event: GameEvent);
+
+    /// Asks the player what to do on their turn against `monster`. `None`
+    /// leaves it to auto-battle, which always attacks.
+    fn choose_action(
+        &mut self,
+        _world: &World,
+        _player: &Player,
+        _monster: &Monster,
+    ) -> Option<Action> {
+        None
+    }
+}
+
+/// Ignores every event, for running the game without any output.
+pub struct Silent;
+
+impl Renderer for Silent {
+    fn render(&mut self, _event: GameEvent) {}
+}
+
+/// Chance for the player to flee `monster`, better the more they outlevel it.
+/// There's always some chance either way.
+pub fn flee_chance(player: &Player, monster: &Monster) -> f64 {
+    let levels = player.level() as f64 - monster.level() as f64;
+    (FLEE_CHANCE + levels * FLEE_CHANCE_PER_LEVEL).clamp(0.05, 0.95)
+}
+
+/// Generates the two caves the player chooses between. One is always easy.
+pub fn generate_caves<R>(world: &mut World, player: &Player, rng: &mut R) -> Vec<Cave>
+where
+    R: Rng,
+{
+    let harder_cave_difficulty = CaveDifficulty::random(rng);
+    let easy_cave = world.new_cave(player, rng, CaveDifficulty::Easy);
+    // Two caves of the same theme would be hard to tell apart
+    let harder_cave = loop {
+        let cave = world.new_cave(player, rng, harder_cave_difficulty);
+        if cave.theme != easy_cave.theme || world.themes().len() < 2 {
+            break cave;
+        }
+    };
+    let mut caves = vec![easy_cave, harder_cave];
+    caves.shuffle(rng); // Ensure random spacial distribution of hard caves
+    caves
+}
+
+/// Gathers [`CLUES_PER_CAVE`] clues about `cave`, each about something
+/// different. Misread clues point to monsters that aren't there and to the
+/// wrong difficulty.
+pub fn scout_cave<R>(world: &World, player: &Player, cave: &Cave, rng: &mut R) -> Vec<Clue>
+where
+    R: Rng,
+{
+    let monsters: Vec<&Monster> = cave.monsters().collect();
+    // Sounds, smell, tracks and a glimpse, in random order
+    let mut kinds = [0, 1, 2, 3];
+    kinds.shuffle(rng);
+
+    kinds
+        .iter()
+        .take(CLUES_PER_CAVE)
+        .map(|kind| {
+            let accurate = rng.gen_bool(player.clue_accuracy());
+            let difficulty = if accurate {
+                cave.difficulty
+            } else {
+                cave.difficulty.opposite()
+            };
+            let monster = if accurate {
+                monsters.choose(rng).map(|monster| (*monster).clone())
+            } else {
+                let max_rarity = match difficulty {
+                    CaveDifficulty::Easy => Rarity::from_level(player.level()),
+                    CaveDifficulty::Hard => Rarity::Legendary,
+                };
+                world.spawn_monster(max_rarity, rng)
+            };
+            let many = if accurate {
+                monsters.len() > 1
+            } else {
+                rng.gen()
+            };
+
+            match (kind, monster) {
+                (0, _) => Clue::Sounds { difficulty },
+                (_, None) => Clue::Silence,
+                (1, Some(monster)) => Clue::Smell { monster },
+                (2, Some(monster)) => Clue::Tracks { monster, many },
+                (_, Some(monster)) => Clue::Glimpse { monster },
+            }
+        })
+        .collect()
+}
+
+/// Starts an expedition into `cave` at its entrance.
+pub fn enter_cave(world: &mut World, name: &str, renderer: &mut dyn Renderer) -> Expedition {
+    renderer.render(GameEvent::CaveEntered { name });
+    world.stats.caves += 1;
+    Expedition {
+        room: Cave::ENTRANCE,
+        explored: false,
+        reward: CaveReward {
+            xp: 0,
+            gold: 0,
+            loot: RawInventory::new(),
+            drops: Vec::new(),
+        },
+    }
+}
+
+/// Moves the player into `room`, fights every monster in it and, if they're
+/// all slain, picks up its loot.
+pub fn explore_room<R>(
+    world: &mut World,
+    player: &mut Player,
+    cave: &mut Cave,
+    expedition: &mut Expedition,
+    room: usize,
+    rng: &mut R,
+    renderer: &mut dyn Renderer,
+) -> RoomResult
+where
+    R: Rng,
+{
+    expedition.room = room;
+    if room != Cave::ENTRANCE && !expedition.explored {
+        expedition.explored = true;
+        expedition.reward.xp += MIN_CAVE_XP;
+    }
+    let room = &mut cave.rooms[room];
+    renderer.render(GameEvent::RoomEntered {
+        kind: room.kind,
+        visited: room.visited,
+    });
+
+    if !room.visited {
+        world.stats.monsters += room.monsters.len() as u64;
+        if room.monsters.is_empty() && room.kind != RoomKind::Entrance {
+            renderer.render(GameEvent::NoEnemies);
+        }
+    }
+    room.visited = true;
+
+    let mut escaped = Vec::new();
+    let mut fighting: VecDeque<Monster> = std::mem::take(&mut room.monsters).into();
+    while let Some(mut monster) = fighting.pop_front() {
+        let difficult = monster.is_difficult(player.level());
+        if monster.boss() {
+            renderer.render(GameEvent::BossIntro { monster: &monster });
+        } else {
+            renderer.render(GameEvent::Encounter {
+                monster: &monster,
+                difficult,
+            });
+        }
+        for ability in monster.abilities() {
+            renderer.render(GameEvent::AbilityRevealed {
+                monster: &monster,
+                ability: &ability.id,
+            });
+        }
+
+        // Roll for initiative
+        let initiative = ((player.level().max(1)) as f64 / (monster.level() * 2) as f64
+            * player.initiative())
+        .clamp(0.0, 1.0);
+        let mut player_turn = rng.gen_bool(initiative);
+        renderer.render(GameEvent::Initiative {
+            monster: &monster,
+            player_first: player_turn,
+        });
+
+        // Monster drastically outclasses player, roll to escape
+        if !player_turn && difficult && rng.gen_bool(ESCAPE_CHANCE) {
+            renderer.render(GameEvent::Retreat { monster: &monster });
+            escaped.push(monster);
+            continue;
+        }
+
+        // Ranged monsters get a shot in before the fight starts
+        if let Some(ability) = monster.abilities().iter().find(|ability| ability.ranged) {
+            renderer.render(GameEvent::RangedShot {
+                monster: &monster,
+                ability: &ability.id,
+            });
+            if !monster_attack(world, player, &monster, rng, renderer) {
+                return RoomResult::Died;
+            }
+        }
+
+        let mut monster_attacked = false;
+        let mut summoned = false;
+        loop {
+            if player_turn {
+                player.lower_guard();
+                let regeneration = player
+                    .abilities()
+                    .iter()
+                    .find(|ability| ability.regeneration > 0.0)
+                    .map(|ability| {
+                        let hp = (player.hp() as f64 * ability.regeneration).ceil() as u64;
+                        (ability.id.clone(), hp.min(player.damage()))
+                    });
+                if let Some((ability, hp)) = regeneration.filter(|(_, hp)| *hp > 0) {
+                    player.heal(hp);
+                    renderer.render(GameEvent::Recovered {
+                        ability: &ability,
+                        hp,
+                    });
+                }
+
+                let stunned = player.stunned();
+                for (effect, damage, ended) in player.tick_effects(Tick::Round) {
+                    renderer.render(GameEvent::EffectTicked { effect, damage });
+                    if ended {
+                        renderer.render(GameEvent::EffectEnded { effect });
+                    }
+                }
+                if !survive(world, player, renderer) {
+                    return RoomResult::Died;
+                }
+                if stunned {
+                    player_turn = !player_turn;
+                    continue;
+                }
+
+                match renderer.choose_action(world, player, &monster) {
+                    Some(Action::Attack) | None => {}
+                    Some(Action::Defend) => {
+                        player.defend();
+                        renderer.render(GameEvent::Defend);
+                        player_turn = !player_turn;
+                        continue;
+                    }
+                    Some(Action::Use { item }) => {
+                        let item = world.get_item(&item);
+                        match item.map(|item| (item, player.use_item(item))) {
+                            Some((item, Ok(used))) => renderer.render(GameEvent::ItemUsed {
+                                item,
+                                cured: used.cured,
+                                granted: used.granted,
+                            }),
+                            _ => renderer.render(GameEvent::ItemUseFailed { item }),
+                        }
+                        player_turn = !player_turn;
+                        continue;
+                    }
+                    Some(Action::Flee) => {
+                        if rng.gen_bool(flee_chance(player, &monster)) {
+                            renderer.render(GameEvent::Retreat { monster: &monster });
+                            escaped.push(monster);
+                            break;
+                        }
+                        renderer.render(GameEvent::FleeFailed { monster: &monster });
+                        player_turn = !player_turn;
+                        continue;
+                    }
+                }
+
+                let dodge = monster
+                    .abilities()
+                    .iter()
+                    .find(|ability| rng.gen_bool(ability.evasion.clamp(0.0, 1.0)));
+                if let Some(ability) = dodge {
+                    renderer.render(GameEvent::Dodged {
+                        monster: &monster,
+                        ability: &ability.id,
+                    });
+                    player_turn = !player_turn;
+                    continue;
+                }
+
+                let damage = monster.add_damage(player.attack_damage(world, rng));
+                if monster.dead() {
+                    renderer.render(GameEvent::Slain {
+                        monster: &monster,
+                        first_strike: !monster_attacked,
+                    });
+                    let xp = monster.level() * MONSTER_XP_PER_LEVEL;
+                    if monster.boss() {
+                        renderer.render(GameEvent::BossDefeated { monster: &monster });
+                        world.stats.bosses.push(monster.id().to_string());
+                        expedition.reward.xp += xp * BOSS_XP_MULTIPLIER;
+                    } else {
+                        expedition.reward.xp += xp;
+                    }
+                    let drops = world.roll_drops(&monster, rng);
+                    if !drops.is_empty() {
+                        expedition.reward.drops.push((monster, drops));
+                    }
+                    break;
+                }
+                renderer.render(GameEvent::Attack {
+                    monster: &monster,
+                    weapon: player.weapon(world),
+                    damage,
+                });
+
+                // A single blow can push a boss through several phases
+                while let Some(phase) = world
+                    .get_boss(monster.id())
+                    .filter(|_| monster.boss())
+                    .and_then(|boss| boss.next_phase(&monster))
+                {
+                    let abilities = phase
+                        .abilities
+                        .iter()
+                        .filter_map(|ability| world.abilities().get(ability).cloned())
+                        .collect();
+                    monster.enter_phase(abilities);
+                    renderer.render(GameEvent::BossPhase {
+                        monster: &monster,
+                        phase: &phase.id,
+                    });
+                }
+
+                let hp_left = monster.hp_remaining() as f64 / monster.hp() as f64;
+                let flee = monster.abilities().iter().find(|ability| {
+                    hp_left < ability.flees_below && rng.gen_bool(Ability::FLEE_CHANCE)
+                });
+                if let Some(ability) = flee {
+                    renderer.render(GameEvent::Fled {
+                        monster: &monster,
+                        ability: &ability.id,
+                    });
+                    escaped.push(monster);
+                    break;
+                }
+            } else {
+                let regeneration = monster
+                    .abilities()
+                    .iter()
+                    .find(|ability| ability.regeneration > 0.0)
+                    .map(|ability| {
+                        let hp = (monster.hp() as f64 * ability.regeneration).ceil() as u64;
+                        (
+                            ability.id.clone(),
+                            hp.min(monster.hp() - monster.hp_remaining()),
+                        )
+                    });
+                if let Some((ability, hp)) = regeneration.filter(|(_, hp)| *hp > 0) {
+                    monster.heal(hp);
+                    renderer.render(GameEvent::Regenerated {
+                        monster: &monster,
+                        ability: &ability,
+                        hp,
+                    });
+                }
+
+                let summoner = monster
+                    .abilities()
+                    .iter()
+                    .find(|ability| !summoned && rng.gen_bool(ability.summons.clamp(0.0, 1.0)));
+                let summon = summoner.and_then(|ability| {
+                    world
+                        .spawn_monster(monster.rarity(), rng)
+                        .map(|summon| (ability.id.clone(), summon))
+                });
+                if let Some((ability, summon)) = summon {
+                    // Summoned monsters join the fight once this one is dealt with
+                    renderer.render(GameEvent::Summoned {
+                        monster: &monster,
+                        ability: &ability,
+                        summoned: &summon,
+                    });
+                    world.stats.monsters += 1;
+                    fighting.push_back(summon);
+                    summoned = true;
+                } else if !monster_attack(world, player, &monster, rng, renderer) {
+                    return RoomResult::Died;
+                }
+
+                if !monster_attacked {
+                    renderer.render(GameEvent::Taunt { monster: &monster });
+                }
+                monster_attacked = true;
+            }
+            player_turn = !player_turn;
+        }
+    }
+    room.monsters = escaped;
+
+    // Loot can only be picked up once nothing is guarding it
+    if room.monsters.is_empty() {
+        expedition.reward.gold += std::mem::take(&mut room.gold);
+        for (item, count) in std::mem::take(&mut room.loot) {
+            *expedition.reward.loot.entry(item).or_insert(0) += count;
+        }
+    }
+    RoomResult::Survived
+}
+
+/// Has `monster` hit the player. Returns whether they survived.
+fn monster_attack<R>(
+    world: &World,
+    player: &mut Player,
+    monster: &Monster,
+    rng: &mut R,
+    renderer: &mut dyn Renderer,
+) -> bool
+where
+    R: Rng,
+{
+    let evade = player
+        .abilities()
+        .iter()
+        .find(|ability| rng.gen_bool(ability.evasion.clamp(0.0, 1.0)));
+    if let Some(ability) = evade {
+        renderer.render(GameEvent::Evaded {
+            monster,
+            ability: &ability.id,
+        });
+        return true;
+    }
+
+    let damage = monster.damage(rng);
+    let applied_damage = player.add_damage(damage, world);
+    renderer.render(GameEvent::Attacked {
+        monster,
+        damage: applied_damage,
+    });
+    let inflicts = world
+        .get_monster(monster.id())
+        .map(|potential| potential.inflicts.as_slice())
+        .unwrap_or_default();
+    for inflict in inflicts {
+        if rng.gen_bool(inflict.chance.clamp(0.0, 1.0)) {
+            player.afflict(inflict.effect, inflict.duration);
+            renderer.render(GameEvent::Afflicted {
+                monster,
+                effect: inflict.effect,
+            });
+        }
+    }
+
+    survive(world, player, renderer)
+}
+
+/// Drinks potions if the player is dying. Returns whether they made it.
+fn survive(world: &World, player: &mut Player, renderer: &mut dyn Renderer) -> bool {
+    if let Some(potions_used) = player.auto_heal(world) {
+        for potion in potions_used {
+            renderer.render(GameEvent::PotionUsed {
+                potion: world
+                    .get_item(&potion)
+                    .expect("Potion ID pulled directly from world.items"),
+            });
+        }
+    }
+    if player.dead() {
+        renderer.render(GameEvent::Died);
+        return false;
+    }
+    true
+}
+
+/// Explores every room in `cave` in order, then leaves.
+pub fn resolve_cave<R>(
+    world: &mut World,
+    player: &mut Player,
+    cave: &mut Cave,
+    name: &str,
+    rng: &mut R,
+    renderer: &mut dyn Renderer,
+) -> CaveResult
+where
+    R: Rng,
+{
+    let mut expedition = enter_cave(world, name, renderer);
+    for room in 0..cave.rooms.len() {
+        if let RoomResult::Died =
+            explore_room(world, player, cave, &mut expedition, room, rng, renderer)
+        {
+            return CaveResult::Died;
+        }
+    }
+    CaveResult::Survived {
+        reward: expedition.reward,
+    }
+}
+
+/// Hands the reward for a survived cave to the player.
+pub fn claim_reward(
+    world: &World,
+    player: &mut Player,
+    reward: CaveReward,
+    renderer: &mut dyn Renderer,
+) {
+    let levels = player.add_xp(reward.xp);
+    player.add_gold(reward.gold);
+    let items = |inventory: &RawInventory| -> Vec<(&Item, u32)> {
+        inventory
+            .iter()
+            .map(|(item, count)| {
+                (
+                    world
+                        .get_item(item)
+                        .expect("world.items should not have mutated"),
+                    *count,
+                )
+            })
+            .collect()
+    };
+    for (item, count) in reward
+        .loot
+        .iter()
+        .chain(reward.drops.iter().flat_map(|(_, drops)| drops))
+    {
+        for _ in 0..*count {
+            player.add_item(item);
+        }
+    }
+
+    renderer.render(GameEvent::Reward {
+        xp: reward.xp,
+        gold: reward.gold,
+        loot: items(&reward.loot),
+        drops: reward
+            .drops
+            .iter()
+            .map(|(monster, drops)| (monster, items(drops)))
+            .collect(),
+    });
+
+    // Looted gear fills empty slots on its own, best first, and the player is
+    // told about anything better than what they have on
+    let mut gear: Vec<&Item> = items(&reward.loot)
+        .into_iter()
+        .chain(reward.drops.iter().flat_map(|(_, drops)| items(drops)))
+        .map(|(item, _)| item)
+        .filter(|item| item.slot().is_some())
+        .collect();
+    gear.sort_by_key(|item| std::cmp::Reverse(item.rating()));
+    for item in gear {
+        let slot = item.slot().expect("Only gear with a slot was kept");
+        match player.equipped(world, slot) {
+            None => {
+                player.equip(item).expect("Item was just looted");
+                renderer.render(GameEvent::Equipped { item });
+            }
+            Some(equipped) if item.rating() > equipped.rating() => {
+                renderer.render(GameEvent::BetterGear { item, equipped });
+            }
+            Some(_) => {}
+        }
+    }
+
+    // Effects from combat wear off on the way out, the rest linger
+    for (effect, damage, ended) in player.tick_effects(Tick::Cave) {
+        renderer.render(GameEvent::EffectTicked { effect, damage });
+        if ended {
+            renderer.render(GameEvent::EffectEnded { effect });
+        }
+    }
+    for effect in player.clear_effects(Tick::Round) {
+        renderer.render(GameEvent::EffectEnded { effect });
+    }
+
+    if !levels.is_empty() {
+        let gained = levels.end - levels.start;
+        player.heal((player.hp() as f64 * LEVEL_UP_HEAL * gained as f64).round() as u64);
+        renderer.render(GameEvent::LevelUp {
+            gained,
+            level: player.level(),
+            hp: player.hp(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::entities::{Level, Room};
+    use crate::interface::GameRng;
+
+    /// An empty cave: the entrance with a passage to one other room.
+    fn cave() -> Cave {
+        let room = |kind, passages| Room {
+            kind,
+            loot: RawInventory::new(),
+            gold: 0,
+            monsters: Vec::new(),
+            passages,
+            visited: false,
+        };
+        Cave {
+            theme: "test".to_string(),
+            name: "Test Cave".to_string(),
+            biome: "test".to_string(),
+            difficulty: CaveDifficulty::Easy,
+            rooms: vec![
+                room(RoomKind::Entrance, vec![1]),
+                room(RoomKind::Corridor, vec![Cave::ENTRANCE]),
+            ],
+        }
+    }
+
+    #[test]
+    fn leaving_from_the_entrance_earns_no_xp() {
+        let mut world = World::new();
+        let mut player = Player::default();
+        let mut cave = cave();
+        let mut rng = GameRng::seed_from_u64(0);
+
+        let mut expedition = enter_cave(&mut world, "Test Cave", &mut Silent);
+        let result = explore_room(
+            &mut world,
+            &mut player,
+            &mut cave,
+            &mut expedition,
+            Cave::ENTRANCE,
+            &mut rng,
+            &mut Silent,
+        );
+        assert!(matches!(result, RoomResult::Survived));
+        assert_eq!(expedition.reward.xp, 0);
+    }
+
+    #[test]
+    fn going_past_the_entrance_earns_cave_xp_once() {
+        let mut world = World::new();
+        let mut player = Player::default();
+        let mut cave = cave();
+        let mut rng = GameRng::seed_from_u64(0);
+
+        let mut expedition = enter_cave(&mut world, "Test Cave", &mut Silent);
+        for room in [Cave::ENTRANCE, 1, Cave::ENTRANCE, 1] {
+            explore_room(
+                &mut world,
+                &mut player,
+                &mut cave,
+                &mut expedition,
+                room,
+                &mut rng,
+                &mut Silent,
+            );
+        }
+        assert_eq!(expedition.reward.xp, MIN_CAVE_XP);
+    }
+
+    #[test]
+    fn leaving_a_cave_ticks_lingering_effects_and_ends_combat_ones() {
+        let world = World::new();
+        let mut player = Player::default();
+        player.afflict(Effect::Poison, 1);
+        player.afflict(Effect::Bleeding, 3);
+        let reward = CaveReward {
+            xp: 0,
+            gold: 0,
+            loot: RawInventory::new(),
+            drops: Vec::new(),
+        };
+
+        claim_reward(&world, &mut player, reward, &mut Silent);
+        let poison = (player.hp() as f64 * Effect::Poison.damage()).ceil() as u64;
+        assert_eq!(player.hp_remaining(), player.hp() - poison);
+        assert!(player.effects().is_empty());
+    }
+
+    /// Notes which looted gear was put on, and which was only pointed out.
+    #[derive(Default)]
+    struct Gear {
+        equipped: Vec<String>,
+        better: Vec<String>,
+    }
+
+    impl Renderer for Gear {
+        fn render(&mut self, event: GameEvent) {
+            match event {
+                GameEvent::Equipped { item } => self.equipped.push(item.id().clone()),
+                GameEvent::BetterGear { item, .. } => self.better.push(item.id().clone()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn looted_gear_fills_empty_slots_and_better_gear_is_pointed_out() {
+        let mut world = World::new();
+        world
+            .load_items_from(
+                r#"[
+                    { "id": "shiv", "name": "Shiv", "kind": "weapon", "damage": 5, "value": 5 },
+                    { "id": "sword", "name": "Sword", "kind": "weapon", "damage": 9, "value": 50 },
+                    { "id": "axe", "name": "Axe", "kind": "weapon", "damage": 12, "value": 80 },
+                    { "id": "cap", "name": "Cap", "kind": "armor", "limb": "head", "defense": 2, "value": 5 }
+                ]"#
+                .as_bytes(),
+            )
+            .unwrap();
+        let mut player = Player::default();
+        player.add_item("sword");
+        player.auto_equip(&world);
+        let loot = |items: &[&str]| -> RawInventory {
+            items.iter().map(|item| (item.to_string(), 1)).collect()
+        };
+        let reward = CaveReward {
+            xp: 0,
+            gold: 0,
+            loot: loot(&["shiv", "cap"]),
+            drops: Vec::new(),
+        };
+
+        let mut gear = Gear::default();
+        claim_reward(&world, &mut player, reward, &mut gear);
+        assert_eq!(gear.equipped, vec!["cap"]);
+        assert!(gear.better.is_empty());
+
+        let reward = CaveReward {
+            xp: 0,
+            gold: 0,
+            loot: loot(&["axe"]),
+            drops: Vec::new(),
+        };
+        let mut gear = Gear::default();
+        claim_reward(&world, &mut player, reward, &mut gear);
+        assert!(gear.equipped.is_empty());
+        assert_eq!(gear.better, vec!["axe"]);
+        assert_eq!(player.weapon(&world).unwrap().id(), "sword");
+    }
+
+    /// Notes the boss's phases and its max HP as it enters each one.
+    #[derive(Default)]
+    struct Phases {
+        entered: Vec<(String, u64)>,
+        defeated: bool,
+    }
+
+    impl Renderer for Phases {
+        fn render(&mut self, event: GameEvent) {
+            match event {
+                GameEvent::BossPhase { monster, phase } => {
+                    self.entered.push((phase.to_string(), monster.hp()))
+                }
+                GameEvent::BossDefeated { .. } => self.defeated = true,
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn bosses_go_through_their_phases_without_changing_max_hp() {
+        let mut world = World::new();
+        world
+            .load_abilities_from(
+                r#"[
+                    { "id": "frail", "hp": 0.5 },
+                    { "id": "enraged", "damage": 1.5 }
+                ]"#
+                .as_bytes(),
+            )
+            .unwrap();
+        world
+            .load_monsters_from(
+                r#"[{ "id": "ogre", "name": "Ogre", "generic": false, "rarity": "petty" }]"#
+                    .as_bytes(),
+            )
+            .unwrap();
+        world
+            .load_bosses_from(
+                r#"[{
+                    "id": "ogre",
+                    "hp": 10.0,
+                    "phases": [
+                        { "id": "wounded", "below": 0.6, "abilities": ["frail"] },
+                        { "id": "desperate", "below": 0.3, "abilities": ["enraged"] }
+                    ]
+                }]"#
+                .as_bytes(),
+            )
+            .unwrap();
+        let mut rng = GameRng::seed_from_u64(7);
+        let boss = world.get_boss("ogre").unwrap().spawn(
+            world.get_monster("ogre").unwrap(),
+            world.abilities(),
+            &mut rng,
+        );
+        let hp = boss.hp();
+        let mut cave = cave();
+        cave.rooms[1].kind = RoomKind::Boss;
+        cave.rooms[1].monsters.push(boss);
+        let mut player = Player::default();
+        player.add_xp(6400);
+
+        let mut phases = Phases::default();
+        let mut expedition = enter_cave(&mut world, "Test Cave", &mut phases);
+        let result = explore_room(
+            &mut world,
+            &mut player,
+            &mut cave,
+            &mut expedition,
+            1,
+            &mut rng,
+            &mut phases,
+        );
+        assert!(matches!(result, RoomResult::Survived));
+        assert!(phases.defeated);
+        assert_eq!(
+            phases.entered,
+            vec![("wounded".to_string(), hp), ("desperate".to_string(), hp)]
+        );
+        assert_eq!(world.stats.bosses, vec!["ogre".to_string()]);
+    }
+
+    /// Tries to use `item` on the first turn, then leaves the fight to
+    /// auto-battle.
+    struct UseOnce {
+        item: Option<String>,
+        failed: bool,
+    }
+
+    impl Renderer for UseOnce {
+        fn render(&mut self, event: GameEvent) {
+            if let GameEvent::ItemUseFailed { .. } = event {
+                self.failed = true;
+            }
+        }
+
+        fn choose_action(
+            &mut self,
+            _world: &World,
+            _player: &Player,
+            _monster: &Monster,
+        ) -> Option<Action> {
+            self.item.take().map(|item| Action::Use { item })
+        }
+    }
+
+    /// Fights a rat, trying to use `item` on the first turn. Returns whether
+    /// the use failed, and the player afterwards.
+    fn fight_using(item: &str) -> (bool, Player) {
+        let mut world = World::new();
+        world
+            .load_items_from(
+                r#"[{ "id": "shiv", "name": "Shiv", "kind": "weapon", "damage": 5, "value": 5 }]"#
+                    .as_bytes(),
+            )
+            .unwrap();
+        world
+            .load_monsters_from(
+                r#"[{ "id": "rat", "name": "Rat", "generic": true, "rarity": "petty" }]"#
+                    .as_bytes(),
+            )
+            .unwrap();
+        let mut rng = GameRng::seed_from_u64(0);
+        let mut cave = cave();
+        cave.rooms[1]
+            .monsters
+            .push(world.spawn_monster(Rarity::Petty, &mut rng).unwrap());
+        let mut player = Player::default();
+        player.add_xp(6400);
+        player.add_item("shiv");
+
+        let mut renderer = UseOnce {
+            item: Some(item.to_string()),
+            failed: false,
+        };
+        let mut expedition = enter_cave(&mut world, "Test Cave", &mut renderer);
+        explore_room(
+            &mut world,
+            &mut player,
+            &mut cave,
+            &mut expedition,
+            1,
+            &mut rng,
+            &mut renderer,
+        );
+        (renderer.failed, player)
+    }
+
+    #[test]
+    fn using_an_unusable_item_is_reported() {
+        let (failed, player) = fight_using("shiv");
+        assert!(failed);
+        assert_eq!(player.has_item("shiv"), Some(1));
+    }
+
+    #[test]
+    fn using_an_unknown_item_is_reported() {
+        let (failed, _) = fight_using("no-such-item");
+        assert!(failed);
+    }
+}
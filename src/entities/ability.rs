@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Something that sets a monster or class apart in combat: stat modifiers, and
+/// behaviors the combat code acts on. Monsters and classes list abilities by
+/// id, though players only get the stat modifiers, evasion and regeneration.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Ability {
+    pub id: String,
+    /// Multiplies the max HP of whoever has it
+    #[serde(default = "Ability::unchanged")]
+    pub hp: f64,
+    /// Multiplies the damage they deal
+    #[serde(default = "Ability::unchanged")]
+    pub damage: f64,
+    /// Fraction of the damage they take that they shrug off
+    #[serde(default)]
+    pub defense: f64,
+    /// Chance to dodge each attack
+    #[serde(default)]
+    pub evasion: f64,
+    /// Fraction of their max HP they heal at the start of their turns
+    #[serde(default)]
+    pub regeneration: f64,
+    /// Whether a monster gets a free shot before the fight starts
+    #[serde(default)]
+    pub ranged: bool,
+    /// Chance for a monster to call in help instead of attacking, once a fight
+    #[serde(default)]
+    pub summons: f64,
+    /// Fraction of its max HP below which a monster may run away
+    #[serde(default)]
+    pub flees_below: f64,
+}
+
+impl Ability {
+    /// Chance for a monster below its `flees_below` threshold to get away
+    pub const FLEE_CHANCE: f64 = 0.5;
+
+    fn unchanged() -> f64 {
+        1.0
+    }
+}
@@ -0,0 +1,70 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+
+use super::{
+    ability::Ability,
+    monster::{Monster, PotentialMonster},
+    Rarity,
+};
+
+/// A stage of a boss fight, entered once the boss is hurt badly enough.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Phase {
+    /// Names the phase's line, `bosses.<boss>.<phase>`
+    pub id: String,
+    /// Fraction of its max HP the boss has to drop below
+    pub below: f64,
+    /// Ids of the abilities the boss gains
+    #[serde(default)]
+    pub abilities: Vec<String>,
+}
+
+/// A unique monster that can wait at the end of a hard cave. Bosses are only
+/// fought once a run.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Boss {
+    /// Id of the unique monster that is the boss
+    pub id: String,
+    /// Multiplies the monster's max HP
+    #[serde(default = "Boss::default_hp")]
+    pub hp: f64,
+    /// In order, from the first the boss enters
+    #[serde(default)]
+    pub phases: Vec<Phase>,
+}
+
+impl Boss {
+    /// Chance for a hard cave to end in a boss room
+    pub const CHANCE: f64 = 0.25;
+
+    fn default_hp() -> f64 {
+        3.0
+    }
+
+    /// Spawns `monster` as this boss, at its strongest.
+    pub fn spawn<R>(
+        &self,
+        monster: &PotentialMonster,
+        abilities: &BTreeMap<String, Ability>,
+        rng: &mut R,
+    ) -> Monster
+    where
+        R: Rng,
+    {
+        let mut boss = monster.spawn(Rarity::Legendary, abilities, rng);
+        boss.make_boss(self.hp);
+        boss
+    }
+
+    /// The phase `monster` enters next, if it's hurt badly enough for it.
+    pub fn next_phase(&self, monster: &Monster) -> Option<&Phase> {
+        let hp_left = monster.hp_remaining() as f64 / monster.hp() as f64;
+        self.phases
+            .get(monster.phase())
+            .filter(|phase| hp_left < phase.below)
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use super::RawInventory;
+
+/// What kind of adventurer the player is, picked at the start of a run.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Class {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Items the player starts with, and how many of each
+    #[serde(default)]
+    pub items: RawInventory,
+    /// Multiplies the player's max HP
+    #[serde(default = "Class::unchanged")]
+    pub hp: f64,
+    /// Multiplies how much damage the player's armor blocks
+    #[serde(default = "Class::unchanged")]
+    pub defense: f64,
+    /// Multiplies the player's chance to strike first
+    #[serde(default = "Class::unchanged")]
+    pub initiative: f64,
+    /// Ids of the abilities the player fights with
+    #[serde(default)]
+    pub abilities: Vec<String>,
+}
+
+impl Class {
+    fn unchanged() -> f64 {
+        1.0
+    }
+}
+
+impl Default for Class {
+    /// Used when no classes are loaded, and for players from before there
+    /// were any.
+    fn default() -> Self {
+        Self {
+            id: "adventurer".into(),
+            name: "Adventurer".into(),
+            description: String::new(),
+            items: RawInventory::new(),
+            hp: 1.0,
+            defense: 1.0,
+            initiative: 1.0,
+            abilities: Vec::new(),
+        }
+    }
+}
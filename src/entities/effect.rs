@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// A lasting condition on the player, counted down in ticks.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Poison,
+    Bleeding,
+    Burning,
+    /// Costs the player their next turn
+    Stun,
+}
+
+/// When an effect counts down.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tick {
+    /// At the start of each of the player's turns in combat
+    Round,
+    /// Once a cave is over
+    Cave,
+}
+
+impl Effect {
+    /// Name used for the effect in content and locale files.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Poison => "poison",
+            Self::Bleeding => "bleeding",
+            Self::Burning => "burning",
+            Self::Stun => "stun",
+        }
+    }
+
+    pub fn tick(&self) -> Tick {
+        match self {
+            Self::Poison => Tick::Cave,
+            Self::Bleeding | Self::Burning | Self::Stun => Tick::Round,
+        }
+    }
+
+    /// Fraction of the player's max HP lost every tick
+    pub fn damage(&self) -> f64 {
+        match self {
+            Self::Poison => 0.1,
+            Self::Bleeding => 0.03,
+            Self::Burning => 0.05,
+            Self::Stun => 0.0,
+        }
+    }
+}
+
+/// An effect given to whoever uses an item.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Affliction {
+    pub effect: Effect,
+    /// Number of ticks it lasts
+    pub duration: u32,
+}
+
+/// An effect a monster may inflict with each hit that lands.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Inflict {
+    pub effect: Effect,
+    /// Number of ticks it lasts
+    pub duration: u32,
+    pub chance: f64,
+}
@@ -1,8 +1,9 @@
-use colored::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use super::*;
-use crate::colors;
+use super::{
+    effect::{Affliction, Effect},
+    *,
+};
 
 #[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
@@ -14,14 +15,65 @@ pub enum Limb {
     Shield,
 }
 
+/// Where an item is worn or held when equipped
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    Weapon,
+    Head,
+    Body,
+    Hands,
+    Feet,
+    Shield,
+}
+
+impl Slot {
+    /// Localization key for the slot's display name
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Self::Weapon => "slot.weapon",
+            Self::Head => "slot.head",
+            Self::Body => "slot.body",
+            Self::Hands => "slot.hands",
+            Self::Feet => "slot.feet",
+            Self::Shield => "slot.shield",
+        }
+    }
+}
+
+impl From<Limb> for Slot {
+    fn from(limb: Limb) -> Self {
+        match limb {
+            Limb::Head => Self::Head,
+            Limb::Body => Self::Body,
+            Limb::Hands => Self::Hands,
+            Limb::Feet => Self::Feet,
+            Limb::Shield => Self::Shield,
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
-#[serde(tag = "kind")]
+#[serde(tag = "kind", deny_unknown_fields)]
 pub enum ItemKind {
     Weapon { damage: u64 },
     Armor { defense: u64, limb: Limb },
     Potion { hp: u64 },
-    Collectible,
+    // Braces make `deny_unknown_fields` apply to collectibles too
+    Collectible {},
+}
+
+impl ItemKind {
+    /// Name used for the kind in content files.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Weapon { .. } => "weapon",
+            Self::Armor { .. } => "armor",
+            Self::Potion { .. } => "potion",
+            Self::Collectible {} => "collectible",
+        }
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -33,6 +85,15 @@ pub struct Item {
     value: u64,
     #[serde(default = "Rarity::default")]
     rarity: Rarity,
+    /// What sort of item this is, for drop tables to refer to
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Effects using the item gets rid of
+    #[serde(default)]
+    cures: Vec<Effect>,
+    /// Effects using the item brings on
+    #[serde(default)]
+    grants: Vec<Affliction>,
 }
 
 impl Item {
@@ -40,8 +101,10 @@ impl Item {
         &self.id
     }
 
-    pub fn name(&self) -> String {
-        self.name.color(colors::ITEM).to_string()
+    /// The name from the content files. Use [`crate::locale::Lexicon::item`]
+    /// to display it.
+    pub fn raw_name(&self) -> &str {
+        &self.name
     }
 
     pub fn value(&self) -> u64 {
@@ -55,4 +118,44 @@ impl Item {
     pub fn kind(&self) -> ItemKind {
         self.kind
     }
+
+    pub fn cures(&self) -> &[Effect] {
+        &self.cures
+    }
+
+    pub fn grants(&self) -> &[Affliction] {
+        &self.grants
+    }
+
+    /// Whether the item can be used up for its effects alone, i.e. isn't a
+    /// potion.
+    pub fn usable(&self) -> bool {
+        let has_effects = !self.cures.is_empty() || !self.grants.is_empty();
+        has_effects && !matches!(self.kind, ItemKind::Potion { .. })
+    }
+
+    /// Whether the item is tagged with `tag`. Every item is also tagged with
+    /// its kind, e.g. `potion`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.kind.id() == tag || self.tags.iter().any(|own| own == tag)
+    }
+
+    /// The slot this item can be equipped to, if any
+    pub fn slot(&self) -> Option<Slot> {
+        match self.kind {
+            ItemKind::Weapon { .. } => Some(Slot::Weapon),
+            ItemKind::Armor { limb, .. } => Some(limb.into()),
+            _ => None,
+        }
+    }
+
+    /// How good this item is at its job, for comparing items of the same slot
+    pub fn rating(&self) -> u64 {
+        match self.kind {
+            ItemKind::Weapon { damage } => damage,
+            ItemKind::Armor { defense, .. } => defense,
+            ItemKind::Potion { hp } => hp,
+            ItemKind::Collectible {} => 0,
+        }
+    }
 }
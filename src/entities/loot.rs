@@ -0,0 +1,275 @@
+use rand::prelude::*;
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+
+use super::{item::Item, theme::LootBias, Rarity, RawInventory};
+
+/// Inclusive range of how many times something happens.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Count {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Count {
+    fn one() -> Self {
+        Self { min: 1, max: 1 }
+    }
+
+    pub fn roll<R>(&self, rng: &mut R) -> u32
+    where
+        R: Rng,
+    {
+        rng.gen_range(self.min..=self.max.max(self.min))
+    }
+}
+
+/// A drop that always happens: either a specific item, or any item of a
+/// rarity.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GuaranteedDrop {
+    #[serde(default)]
+    pub item: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<Rarity>,
+    #[serde(default = "GuaranteedDrop::one")]
+    pub count: u32,
+}
+
+impl GuaranteedDrop {
+    fn one() -> u32 {
+        1
+    }
+}
+
+/// Something a monster may drop when it's defeated: a specific item, or any
+/// item with a tag.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MonsterDrop {
+    #[serde(default)]
+    pub item: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Chance of dropping anything at all
+    #[serde(default = "MonsterDrop::always")]
+    pub chance: f64,
+    #[serde(default = "Count::one")]
+    pub count: Count,
+}
+
+impl MonsterDrop {
+    fn always() -> f64 {
+        1.0
+    }
+
+    /// Rolls the drop, adding what drops to `loot`.
+    pub fn roll<R>(&self, items: &BTreeMap<String, Item>, loot: &mut RawInventory, rng: &mut R)
+    where
+        R: Rng,
+    {
+        if !rng.gen_bool(self.chance.clamp(0.0, 1.0)) {
+            return;
+        }
+        let item = match (&self.item, &self.tag) {
+            (Some(item), _) if items.contains_key(item) => Some(item.clone()),
+            (None, Some(tag)) => items
+                .values()
+                .filter(|item| item.has_tag(tag))
+                .choose(rng)
+                .map(|item| item.id().clone()),
+            _ => None,
+        };
+        if let Some(item) = item {
+            let count = self.count.roll(rng);
+            if count > 0 {
+                *loot.entry(item).or_insert(0) += count;
+            }
+        }
+    }
+}
+
+/// Decides what loot is found where, e.g. in an easy cave.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LootTable {
+    pub id: String,
+    /// How many items are rolled
+    pub rolls: Count,
+    /// How likely each rarity is to be rolled, relative to the others.
+    /// Rarities left out are never rolled.
+    pub weights: BTreeMap<Rarity, f64>,
+    /// Added to a rarity's weight for every level the player has, so better
+    /// loot turns up as they grow
+    #[serde(default)]
+    pub level_scaling: BTreeMap<Rarity, f64>,
+    /// Dropped on top of the rolls
+    #[serde(default)]
+    pub guaranteed: Vec<GuaranteedDrop>,
+}
+
+impl LootTable {
+    /// Table for caves generated as easy ones
+    pub const EASY_CAVE: &'static str = "cave.easy";
+    /// Table for caves generated as hard ones
+    pub const HARD_CAVE: &'static str = "cave.hard";
+    /// Rolled on top of the cave's table for every unique monster in it
+    pub const UNIQUE_MONSTER: &'static str = "monster.unique";
+    /// Rolled for the room of a boss
+    pub const BOSS: &'static str = "boss";
+
+    /// The weight of `rarity` for a player of `level`, never below 0.
+    pub fn weight(&self, rarity: Rarity, level: u64) -> f64 {
+        let base = self.weights.get(&rarity).copied().unwrap_or(0.0);
+        let scaling = self.level_scaling.get(&rarity).copied().unwrap_or(0.0);
+        (base + scaling * level as f64).max(0.0)
+    }
+
+    /// Rolls the table for a player of `level`, adding what drops to `loot`.
+    /// Items of the same kind stack. `bias` makes some kinds of item more
+    /// likely within the rolled rarity.
+    pub fn roll<R>(
+        &self,
+        items: &BTreeMap<String, Item>,
+        level: u64,
+        bias: &LootBias,
+        loot: &mut RawInventory,
+        rng: &mut R,
+    ) where
+        R: Rng,
+    {
+        let droppable = |item: &&Item| bias.weight(&item.kind()) > 0.0;
+        let of_rarity = |rarity: Rarity| {
+            items
+                .values()
+                .filter(move |item| item.rarity() == rarity)
+                .filter(droppable)
+        };
+        let pick = |rarity: Rarity, rng: &mut R| {
+            of_rarity(rarity)
+                .collect::<Vec<&Item>>()
+                .choose_weighted(rng, |item| bias.weight(&item.kind()))
+                .ok()
+                .map(|item| item.id().clone())
+        };
+
+        // Only rarities that have something to drop can be rolled
+        let rarities: Vec<(Rarity, f64)> = self
+            .weights
+            .keys()
+            .map(|rarity| (*rarity, self.weight(*rarity, level)))
+            .filter(|(rarity, weight)| *weight > 0.0 && of_rarity(*rarity).next().is_some())
+            .collect();
+        for _ in 0..self.rolls.roll(rng) {
+            let rarity = match rarities.choose_weighted(rng, |(_, weight)| *weight) {
+                Ok((rarity, _)) => *rarity,
+                Err(_) => break,
+            };
+            if let Some(item) = pick(rarity, rng) {
+                *loot.entry(item).or_insert(0) += 1;
+            }
+        }
+
+        for drop in &self.guaranteed {
+            let item = match (&drop.item, drop.rarity) {
+                (Some(item), _) if items.contains_key(item) => Some(item.clone()),
+                (None, Some(rarity)) => pick(rarity, rng),
+                _ => None,
+            };
+            if let Some(item) = item {
+                *loot.entry(item).or_insert(0) += drop.count;
+            }
+        }
+    }
+}
+
+impl Default for LootTable {
+    /// Used when no table is loaded: a few items of any rarity.
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            rolls: Count { min: 1, max: 4 },
+            weights: [
+                Rarity::Petty,
+                Rarity::Common,
+                Rarity::Uncommon,
+                Rarity::Rare,
+                Rarity::Legendary,
+            ]
+            .iter()
+            .map(|rarity| (*rarity, 1.0))
+            .collect(),
+            level_scaling: BTreeMap::new(),
+            guaranteed: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::GameRng;
+
+    fn items() -> BTreeMap<String, Item> {
+        let items: Vec<Item> = serde_json::from_str(
+            r#"[
+                { "id": "rock", "name": "Rock", "kind": "collectible", "value": 1, "rarity": "petty" },
+                { "id": "shiv", "name": "Shiv", "kind": "weapon", "damage": 5, "value": 5, "rarity": "common" },
+                { "id": "tonic", "name": "Tonic", "kind": "potion", "hp": 10, "value": 10, "rarity": "common" },
+                { "id": "crown", "name": "Crown", "kind": "collectible", "value": 500, "rarity": "rare" }
+            ]"#,
+        )
+        .unwrap();
+        items
+            .into_iter()
+            .map(|item| (item.id().clone(), item))
+            .collect()
+    }
+
+    fn roll(table: &LootTable, items: &BTreeMap<String, Item>, seed: u64) -> RawInventory {
+        let mut loot = RawInventory::new();
+        let mut rng = GameRng::seed_from_u64(seed);
+        table.roll(items, 10, &LootBias::default(), &mut loot, &mut rng);
+        loot
+    }
+
+    #[test]
+    fn rolls_are_the_same_for_the_same_seed() {
+        let items = items();
+        let table = LootTable::default();
+        for seed in 0..50 {
+            let loot = roll(&table, &items, seed);
+            assert!(!loot.is_empty());
+            assert_eq!(loot, roll(&table, &items, seed));
+        }
+        let rolls: Vec<RawInventory> = (0..50).map(|seed| roll(&table, &items, seed)).collect();
+        assert!(rolls.iter().any(|loot| *loot != rolls[0]));
+    }
+
+    #[test]
+    fn guaranteed_drops_always_drop() {
+        let items = items();
+        let table: LootTable = serde_json::from_str(
+            r#"{
+                "id": "boss",
+                "rolls": { "min": 0, "max": 0 },
+                "weights": {},
+                "guaranteed": [
+                    { "item": "crown" },
+                    { "rarity": "common", "count": 2 },
+                    { "item": "missing" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        for seed in 0..20 {
+            let loot = roll(&table, &items, seed);
+            assert_eq!(loot.get("crown"), Some(&1));
+            assert_eq!(loot.values().sum::<u32>(), 3);
+            assert!(!loot.contains_key("missing"));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use rand::Rng;
+
+use std::fmt;
+
+use super::{item::ItemKind, player::Player, *};
+
+/// Fraction of an item's value the merchant pays for it
+pub const SELL_RATE: f64 = 0.5;
+
+#[derive(Debug)]
+pub enum TradeError {
+    NotEnoughGold,
+    OutOfStock,
+    NotOwned,
+}
+
+impl TradeError {
+    /// Localization key for telling the player what went wrong
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Self::NotEnoughGold => "merchant.too-poor",
+            Self::OutOfStock => "merchant.out-of-stock",
+            Self::NotOwned => "merchant.not-owned",
+        }
+    }
+}
+
+impl fmt::Display for TradeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::NotEnoughGold => write!(f, "not enough gold"),
+            Self::OutOfStock => write!(f, "the merchant has none left"),
+            Self::NotOwned => write!(f, "the player doesn't have the item"),
+        }
+    }
+}
+
+impl std::error::Error for TradeError {}
+
+/// A travelling merchant met between caves.
+pub struct Merchant {
+    stock: RawInventory,
+}
+
+impl Merchant {
+    /// Rolls a fresh stock of gear and potions. Rarer items show up less
+    /// often and in smaller numbers.
+    pub fn new<R>(world: &World, rng: &mut R) -> Self
+    where
+        R: Rng,
+    {
+        let stock = world
+            .items()
+            .values()
+            .filter(|item| {
+                matches!(
+                    item.kind(),
+                    ItemKind::Weapon { .. } | ItemKind::Armor { .. } | ItemKind::Potion { .. }
+                )
+            })
+            .filter_map(|item| {
+                if rng.gen_bool(item.rarity().stock_chance()) {
+                    let count = rng.gen_range(1..=item.rarity().max_stock());
+                    Some((item.id().clone(), count))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Self { stock }
+    }
+
+    /// What the player pays for `item`
+    pub fn price(&self, item: &Item) -> u64 {
+        item.value()
+    }
+
+    /// What the player gets for selling `item`
+    pub fn offer(&self, item: &Item) -> u64 {
+        (item.value() as f64 * SELL_RATE).floor() as u64
+    }
+
+    /// Sells one of `item` to the player, returning the price paid.
+    pub fn buy(&mut self, player: &mut Player, item: &Item) -> Result<u64, TradeError> {
+        let price = self.price(item);
+        if self.has_item(item.id()).is_none() {
+            return Err(TradeError::OutOfStock);
+        }
+        if player.gold() < price {
+            return Err(TradeError::NotEnoughGold);
+        }
+        self.remove_item(item.id())
+            .expect("Item was just checked to be in stock.");
+        player.remove_gold(price);
+        player.add_item(item.id());
+        Ok(price)
+    }
+
+    /// Buys one of `item` from the player, returning the gold paid out.
+    pub fn sell(&mut self, player: &mut Player, item: &Item) -> Result<u64, TradeError> {
+        let offer = self.offer(item);
+        player
+            .remove_item(item.id())
+            .map_err(|_| TradeError::NotOwned)?;
+        player.add_gold(offer);
+        self.add_item(item.id());
+        Ok(offer)
+    }
+}
+
+impl Inventory for Merchant {
+    fn inventory(&self) -> &RawInventory {
+        &self.stock
+    }
+
+    fn mut_inventory(&mut self) -> &mut RawInventory {
+        &mut self.stock
+    }
+}
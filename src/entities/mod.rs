@@ -1,21 +1,30 @@
 use rand::Rng;
 use serde::Deserialize;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::ops::Range;
 
+pub mod ability;
+pub mod boss;
+pub mod class;
+pub mod effect;
 pub mod item;
+pub mod loot;
+pub mod merchant;
 pub mod monster;
 pub mod player;
+pub mod theme;
+mod validate;
 mod world;
 
 use item::{Item, ItemKind, Limb};
+pub use validate::*;
 pub use world::*;
 
 const BASE_HP: u64 = 20;
 
-type RawInventory = HashMap<String, u32>;
+pub type RawInventory = BTreeMap<String, u32>;
 
 #[derive(Debug)]
 pub struct ItemError {}
@@ -39,6 +48,17 @@ pub enum Rarity {
 }
 
 impl Rarity {
+    /// Name used for the rarity in content and locale files.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Petty => "petty",
+            Self::Common => "common",
+            Self::Uncommon => "uncommon",
+            Self::Rare => "rare",
+            Self::Legendary => "legendary",
+        }
+    }
+
     pub fn random<R>(rng: &mut R) -> Self
     where
         R: Rng,
@@ -75,6 +95,38 @@ impl Rarity {
         }
     }
 
+    /// Scales the base HP of monsters of this rarity
+    pub fn hp_multiplier(&self) -> f64 {
+        match *self {
+            Self::Petty => 1.0,
+            Self::Common => 1.2,
+            Self::Uncommon => 1.5,
+            Self::Rare => 2.0,
+            Self::Legendary => 3.0,
+        }
+    }
+
+    /// Chance of a merchant stocking an item of this rarity
+    pub fn stock_chance(&self) -> f64 {
+        match *self {
+            Self::Petty => 0.6,
+            Self::Common => 0.4,
+            Self::Uncommon => 0.25,
+            Self::Rare => 0.1,
+            Self::Legendary => 0.02,
+        }
+    }
+
+    /// Most copies of an item of this rarity a merchant will stock
+    pub fn max_stock(&self) -> u32 {
+        match *self {
+            Self::Petty => 5,
+            Self::Common => 3,
+            Self::Uncommon => 2,
+            Self::Rare | Self::Legendary => 1,
+        }
+    }
+
     pub fn from_level(level: u64) -> Self {
         match level {
             0..=9 => Self::Petty,
@@ -173,7 +225,15 @@ pub trait Inventory {
 pub trait Level {
     fn xp(&self) -> u64;
 
-    fn add_xp(&mut self, amount: u64);
+    fn mut_xp(&mut self) -> &mut u64;
+
+    /// Adds xp and returns the levels newly reached, which is empty if the
+    /// level didn't change.
+    fn add_xp(&mut self, amount: u64) -> Range<u64> {
+        let previous_level = self.level();
+        *self.mut_xp() += amount;
+        (previous_level + 1)..(self.level() + 1)
+    }
 
     fn level(&self) -> u64 {
         ((self.xp() as f64).sqrt() / 8.0).round() as u64
@@ -1,11 +1,14 @@
-use colored::*;
 use rand::Rng;
 use serde::Deserialize;
 
-use super::Rarity;
-use crate::colors;
+use std::collections::BTreeMap;
+
+use super::{ability::Ability, effect::Inflict, loot::MonsterDrop, Rarity};
+
+const BASE_HP: u64 = 2;
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PotentialMonster {
     pub id: String,
     pub name: String,
@@ -14,10 +17,29 @@ pub struct PotentialMonster {
     pub generic: bool,
     pub rarity: Option<Rarity>,
     pub max_level: Option<u64>,
+    /// What kind of creature this is, matched against cave themes
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Rolled when the monster is defeated
+    #[serde(default)]
+    pub drops: Vec<MonsterDrop>,
+    /// Effects the monster's hits may leave the player with
+    #[serde(default)]
+    pub inflicts: Vec<Inflict>,
+    /// Ids of the monster's abilities
+    #[serde(default)]
+    pub abilities: Vec<String>,
 }
 
 impl PotentialMonster {
-    pub fn spawn<R>(&self, mut max_rarity: Rarity, rng: &mut R) -> Monster
+    /// Spawns the monster with whichever of its abilities are in
+    /// `abilities`.
+    pub fn spawn<R>(
+        &self,
+        mut max_rarity: Rarity,
+        abilities: &BTreeMap<String, Ability>,
+        rng: &mut R,
+    ) -> Monster
     where
         R: Rng,
     {
@@ -27,65 +49,61 @@ impl PotentialMonster {
         }
         #[allow(clippy::or_fun_call)]
         let rarity = self.rarity.unwrap_or(Rarity::random(rng).min(max_rarity));
+        let abilities: Vec<Ability> = self
+            .abilities
+            .iter()
+            .filter_map(|ability| abilities.get(ability).cloned())
+            .collect();
         Monster {
+            id: self.id.clone(),
             name: self.name.clone(),
             proper_noun: self.proper_noun,
             generic: self.generic,
             rarity,
             level: rng.gen_range(rarity.level_range()),
+            damage: 0,
+            innate_abilities: abilities.len(),
+            abilities,
+            boss: false,
+            boss_hp: 1.0,
+            phase: 0,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Monster {
+    id: String,
     name: String,
     proper_noun: bool,
     generic: bool,
     rarity: Rarity,
     level: u64,
+    damage: u64,
+    abilities: Vec<Ability>,
+    /// How many of `abilities` the monster spawned with. Only those scale its
+    /// max HP, so entering a boss phase never changes it mid-fight.
+    innate_abilities: usize,
+    boss: bool,
+    /// Multiplies the max HP of bosses
+    boss_hp: f64,
+    /// Number of boss phases entered so far
+    phase: usize,
 }
 
 impl Monster {
-    fn colored_raw_name(&self) -> String {
-        self.name.color(colors::MONSTER).to_string()
-    }
-
-    pub fn name(&self) -> String {
-        if self.generic {
-            match self.rarity {
-                Rarity::Petty => format!("Petty {}", self.name),
-                Rarity::Common => format!("Common {}", self.name),
-                Rarity::Uncommon => format!("Uncommon {}", self.name),
-                Rarity::Rare => format!("Rare {}", self.name),
-                Rarity::Legendary => format!("Legendary {}", self.name),
-            }
-        } else {
-            self.name.clone()
-        }
-        .color(colors::MONSTER)
-        .to_string()
-    }
-
-    pub fn article_name(&self) -> String {
-        if self.generic {
-            match self.rarity {
-                Rarity::Petty => format!("a {}", self.name()),
-                Rarity::Common => format!("a {}", self.name()),
-                Rarity::Uncommon => format!("an {}", self.name()),
-                Rarity::Rare => format!("a {}", self.name()),
-                Rarity::Legendary => format!("a {}", self.name()),
-            }
-        } else {
-            self.proper_name()
-        }
+    pub fn id(&self) -> &str {
+        &self.id
     }
 
-    pub fn proper_name(&self) -> String {
-        if self.proper_noun {
-            self.colored_raw_name()
-        } else {
-            format!("The {}", self.colored_raw_name())
-        }
+    /// The name from the content files. Use [`crate::locale::Lexicon::monster`]
+    /// to display it.
+    pub fn raw_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn proper_noun(&self) -> bool {
+        self.proper_noun
     }
 
     pub fn generic(&self) -> bool {
@@ -100,6 +118,64 @@ impl Monster {
         self.level
     }
 
+    pub fn abilities(&self) -> &[Ability] {
+        &self.abilities
+    }
+
+    pub fn boss(&self) -> bool {
+        self.boss
+    }
+
+    /// Turns the monster into a boss with `hp` times its usual max HP.
+    pub fn make_boss(&mut self, hp: f64) {
+        self.boss = true;
+        self.boss_hp = hp;
+    }
+
+    pub fn phase(&self) -> usize {
+        self.phase
+    }
+
+    /// Moves a boss on to its next phase, gaining `abilities`. Their `hp`
+    /// doesn't apply, see [`Monster::hp`].
+    pub fn enter_phase(&mut self, abilities: Vec<Ability>) {
+        self.phase += 1;
+        self.abilities.extend(abilities);
+    }
+
+    /// Max HP, scaled by the abilities the monster spawned with
+    pub fn hp(&self) -> u64 {
+        let multiplier = self.abilities[..self.innate_abilities].iter().fold(
+            self.rarity.hp_multiplier() * self.boss_hp,
+            |multiplier, ability| multiplier * ability.hp,
+        );
+        (((BASE_HP + self.level) as f64 * multiplier).round() as u64).max(1)
+    }
+
+    pub fn hp_remaining(&self) -> u64 {
+        self.hp().saturating_sub(self.damage)
+    }
+
+    /// Deals `amount` damage to the monster, less whatever its abilities shrug
+    /// off. Returns the damage dealt.
+    pub fn add_damage(&mut self, amount: u64) -> u64 {
+        let taken = self
+            .abilities
+            .iter()
+            .fold(1.0, |taken, ability| taken * (1.0 - ability.defense));
+        let reduced_damage = (amount as f64 * taken).round() as u64;
+        self.damage += reduced_damage;
+        reduced_damage
+    }
+
+    pub fn heal(&mut self, amount: u64) {
+        self.damage = self.damage.saturating_sub(amount)
+    }
+
+    pub fn dead(&self) -> bool {
+        self.damage >= self.hp()
+    }
+
     pub fn is_difficult(&self, player_level: u64) -> bool {
         self.level() / player_level.max(1) > 3 && self.level().saturating_sub(player_level) > 10
     }
@@ -110,6 +186,60 @@ impl Monster {
     {
         let min = (self.level / 10).max(2) as u64;
         let max = (self.level as u64).max(min + 1);
-        rng.gen_range(min..max)
+        let multiplier = self
+            .abilities
+            .iter()
+            .fold(1.0, |multiplier, ability| multiplier * ability.damage);
+        ((rng.gen_range(min..max) as f64 * multiplier).round() as u64).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::interface::GameRng;
+
+    fn armored_skeleton() -> Monster {
+        let abilities: BTreeMap<String, Ability> = serde_json::from_str(
+            r#"{ "armored": { "id": "armored", "hp": 2.0, "defense": 0.5 } }"#,
+        )
+        .unwrap();
+        let skeleton: PotentialMonster = serde_json::from_str(
+            r#"{
+                "id": "skeleton",
+                "name": "Skeleton",
+                "generic": true,
+                "rarity": "common",
+                "abilities": ["armored", "missing"]
+            }"#,
+        )
+        .unwrap();
+        skeleton.spawn(
+            Rarity::Legendary,
+            &abilities,
+            &mut GameRng::seed_from_u64(0),
+        )
+    }
+
+    #[test]
+    fn spawns_with_the_abilities_that_exist() {
+        let skeleton = armored_skeleton();
+        let ids: Vec<&str> = skeleton
+            .abilities()
+            .iter()
+            .map(|ability| ability.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["armored"]);
+    }
+
+    #[test]
+    fn abilities_scale_hp_and_damage_taken() {
+        let mut skeleton = armored_skeleton();
+        let base = (BASE_HP + skeleton.level()) as f64 * Rarity::Common.hp_multiplier();
+        assert_eq!(skeleton.hp(), (base * 2.0).round() as u64);
+        assert_eq!(skeleton.add_damage(10), 5);
+        assert_eq!(skeleton.hp_remaining(), skeleton.hp() - 5);
     }
 }
@@ -1,13 +1,107 @@
-use super::{item::ItemKind, *};
+use serde::Serialize;
 
+use super::{
+    ability::Ability,
+    class::Class,
+    effect::{Effect, Tick},
+    item::{ItemKind, Slot},
+    *,
+};
+
+const UNARMED_DAMAGE: u64 = 5;
+/// Perception every player starts with
+const BASE_PERCEPTION: u64 = 5;
+/// Levels it takes to gain a point of perception
+const LEVELS_PER_PERCEPTION: u64 = 2;
+/// Share of the damage getting through their armor a defending player blocks
+const DEFEND_DAMAGE_REDUCTION: f64 = 0.5;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Player {
     xp: u64,
     damage: u64,
     gold: u64,
     inventory: RawInventory,
+    #[serde(default)]
+    equipment: BTreeMap<Slot, String>,
+    #[serde(default = "Player::base_perception")]
+    perception: u64,
+    /// Active effects and how many ticks each has left
+    #[serde(default)]
+    effects: BTreeMap<Effect, u32>,
+    /// Whether the player is bracing for the next attack. Only lasts a round,
+    /// so it's never saved.
+    #[serde(skip)]
+    defending: bool,
+    #[serde(default)]
+    class: Class,
+    /// The class's abilities, as they were when the run started
+    #[serde(default)]
+    abilities: Vec<Ability>,
+}
+
+/// What using an item did to the player.
+pub struct ItemUse {
+    pub healed: u64,
+    /// Effects the item got rid of, leaving out ones the player didn't have
+    pub cured: Vec<Effect>,
+    pub granted: Vec<Effect>,
 }
 
 impl Player {
+    /// Starts a run as `class`, with its starting items equipped where they
+    /// can be. Items and abilities the world doesn't have are left out, and
+    /// reported when validating.
+    pub fn new(class: &Class, world: &World) -> Self {
+        let mut player = Self {
+            class: class.clone(),
+            abilities: class
+                .abilities
+                .iter()
+                .filter_map(|ability| world.abilities().get(ability).cloned())
+                .collect(),
+            ..Self::default()
+        };
+        for (item, count) in &class.items {
+            if world.get_item(item).is_some() {
+                for _ in 0..*count {
+                    player.add_item(item);
+                }
+            }
+        }
+        player.auto_equip(world);
+        player
+    }
+
+    pub fn class(&self) -> &Class {
+        &self.class
+    }
+
+    pub fn abilities(&self) -> &[Ability] {
+        &self.abilities
+    }
+
+    /// Multiplies the player's chance to strike first
+    pub fn initiative(&self) -> f64 {
+        self.class.initiative
+    }
+
+    fn base_perception() -> u64 {
+        BASE_PERCEPTION
+    }
+
+    /// How good the player is at reading a cave from the outside
+    pub fn perception(&self) -> u64 {
+        self.perception + self.level() / LEVELS_PER_PERCEPTION
+    }
+
+    /// Chance that a clue about a cave is accurate. Even the least perceptive
+    /// players read half of them right.
+    pub fn clue_accuracy(&self) -> f64 {
+        let perception = self.perception() as f64;
+        0.5 + 0.5 * perception / (perception + 10.0)
+    }
+
     pub fn damage(&self) -> u64 {
         self.damage
     }
@@ -24,7 +118,25 @@ impl Player {
     }
 
     pub fn damage_reduction(&self, world: &World) -> f64 {
-        0.12 * self.defense(world) as f64 / 100.0
+        let armor = 0.12 * self.defense(world) as f64 / 100.0 * self.class.defense;
+        let reduction = 1.0
+            - self.abilities.iter().fold(1.0 - armor, |taken, ability| {
+                taken * (1.0 - ability.defense)
+            });
+        if self.defending {
+            reduction + (1.0 - reduction) * DEFEND_DAMAGE_REDUCTION
+        } else {
+            reduction
+        }
+    }
+
+    /// Braces the player for the next attack, until their next turn.
+    pub fn defend(&mut self) {
+        self.defending = true;
+    }
+
+    pub fn lower_guard(&mut self) {
+        self.defending = false;
     }
 
     pub fn heal(&mut self, amount: u64) {
@@ -48,12 +160,7 @@ impl Player {
 
             while self.dead() {
                 if let Some(potion) = potions.pop() {
-                    self.heal(match potion.kind() {
-                        ItemKind::Potion { hp } => hp,
-                        _ => panic!("There should only ever be potions here."),
-                    });
-                    self.remove_item(potion.id())
-                        .expect("Potion will still exist.");
+                    self.drink(potion).expect("Potion will still exist.");
                     potions_used.push(potion.id().to_owned());
                 } else {
                     break;
@@ -68,6 +175,190 @@ impl Player {
         }
     }
 
+    pub fn equipment(&self) -> &BTreeMap<Slot, String> {
+        &self.equipment
+    }
+
+    /// The item in `slot`, if there is one the loaded packs still define.
+    pub fn equipped<'a>(&self, world: &'a World, slot: Slot) -> Option<&'a Item> {
+        self.equipment
+            .get(&slot)
+            .and_then(|item| world.get_item(item))
+    }
+
+    /// Drops items the loaded packs don't define, e.g. ones saved with a pack
+    /// that has since been removed.
+    pub fn forget_unknown_items(&mut self, world: &World) {
+        self.inventory
+            .retain(|item, _| world.get_item(item).is_some());
+        self.equipment
+            .retain(|_, item| world.get_item(item).is_some());
+    }
+
+    pub fn weapon<'a>(&self, world: &'a World) -> Option<&'a Item> {
+        self.equipped(world, Slot::Weapon)
+    }
+
+    /// Moves `item` from the inventory into its slot. Whatever was in the slot
+    /// goes back into the inventory.
+    pub fn equip(&mut self, item: &Item) -> Result<(), ItemError> {
+        let slot = item.slot().ok_or(ItemError {})?;
+        self.remove_item(item.id())?;
+        if let Some(previous) = self.equipment.insert(slot, item.id().clone()) {
+            self.add_item(&previous);
+        }
+        Ok(())
+    }
+
+    /// Moves the item in `slot` back into the inventory.
+    pub fn unequip(&mut self, slot: Slot) -> Result<(), ItemError> {
+        let item = self.equipment.remove(&slot).ok_or(ItemError {})?;
+        self.add_item(&item);
+        Ok(())
+    }
+
+    /// Equips the best item in the inventory for every slot, for players that
+    /// don't manage their own gear.
+    pub fn auto_equip(&mut self, world: &World) {
+        for item in self.inventory_items(world) {
+            if let Some(slot) = item.slot() {
+                let better = match self.equipped(world, slot) {
+                    Some(current) => item.rating() > current.rating(),
+                    None => true,
+                };
+                if better {
+                    self.equip(item).expect("Item pulled from the inventory.");
+                }
+            }
+        }
+    }
+
+    /// Drinks a potion from the inventory, returning the hp it healed.
+    pub fn drink(&mut self, item: &Item) -> Result<u64, ItemError> {
+        if let ItemKind::Potion { .. } = item.kind() {
+            Ok(self.use_item(item)?.healed)
+        } else {
+            Err(ItemError {})
+        }
+    }
+
+    /// Uses up a potion or an item with effects from the inventory.
+    pub fn use_item(&mut self, item: &Item) -> Result<ItemUse, ItemError> {
+        let healed = match item.kind() {
+            ItemKind::Potion { hp } => hp,
+            _ if item.usable() => 0,
+            _ => return Err(ItemError {}),
+        };
+        self.remove_item(item.id())?;
+        self.heal(healed);
+        let cured = item
+            .cures()
+            .iter()
+            .filter(|effect| self.cure(**effect))
+            .copied()
+            .collect();
+        for affliction in item.grants() {
+            self.afflict(affliction.effect, affliction.duration);
+        }
+        Ok(ItemUse {
+            healed,
+            cured,
+            granted: item
+                .grants()
+                .iter()
+                .map(|affliction| affliction.effect)
+                .collect(),
+        })
+    }
+
+    pub fn effects(&self) -> &BTreeMap<Effect, u32> {
+        &self.effects
+    }
+
+    /// Gives the player `effect` for `duration` ticks. An effect they already
+    /// have lasts for whichever is longer.
+    pub fn afflict(&mut self, effect: Effect, duration: u32) {
+        if duration > 0 {
+            let remaining = self.effects.entry(effect).or_insert(0);
+            *remaining = (*remaining).max(duration);
+        }
+    }
+
+    /// Rids the player of `effect`, returning whether they had it.
+    pub fn cure(&mut self, effect: Effect) -> bool {
+        self.effects.remove(&effect).is_some()
+    }
+
+    pub fn stunned(&self) -> bool {
+        self.effects.contains_key(&Effect::Stun)
+    }
+
+    /// Removes every effect that ticks on `tick`, returning them.
+    pub fn clear_effects(&mut self, tick: Tick) -> Vec<Effect> {
+        let cleared: Vec<Effect> = self
+            .effects
+            .keys()
+            .filter(|effect| effect.tick() == tick)
+            .copied()
+            .collect();
+        for effect in &cleared {
+            self.effects.remove(effect);
+        }
+        cleared
+    }
+
+    /// Counts down every effect that ticks on `tick` and applies its damage.
+    /// Returns each effect that ticked with the damage it dealt, and whether it
+    /// has now worn off. Effects that tick once a cave is over can't kill.
+    pub fn tick_effects(&mut self, tick: Tick) -> Vec<(Effect, u64, bool)> {
+        let hp = self.hp();
+        let ticking: Vec<Effect> = self
+            .effects
+            .keys()
+            .filter(|effect| effect.tick() == tick)
+            .copied()
+            .collect();
+        ticking
+            .into_iter()
+            .map(|effect| {
+                let mut damage = (hp as f64 * effect.damage()).ceil() as u64;
+                if tick == Tick::Cave {
+                    damage = damage.min(self.hp_remaining().saturating_sub(1));
+                }
+                self.damage += damage;
+
+                let remaining = self
+                    .effects
+                    .get_mut(&effect)
+                    .expect("Effect pulled from self.effects");
+                *remaining -= 1;
+                let ended = *remaining == 0;
+                if ended {
+                    self.effects.remove(&effect);
+                }
+                (effect, damage, ended)
+            })
+            .collect()
+    }
+
+    /// Rolls the damage of a single attack with the equipped weapon
+    pub fn attack_damage<R>(&self, world: &World, rng: &mut R) -> u64
+    where
+        R: Rng,
+    {
+        let weapon_damage = match self.weapon(world).map(|weapon| weapon.kind()) {
+            Some(ItemKind::Weapon { damage }) => damage,
+            _ => UNARMED_DAMAGE,
+        };
+        // A weapon can have no damage of its own, but every hit deals some
+        let max = (weapon_damage + self.level() * 2).max(1);
+        let multiplier = self
+            .abilities
+            .iter()
+            .fold(1.0, |multiplier, ability| multiplier * ability.damage);
+        ((rng.gen_range((max / 2).max(1)..=max) as f64 * multiplier).round() as u64).max(1)
+    }
+
     pub fn gold(&self) -> u64 {
         self.gold
     }
@@ -76,6 +367,10 @@ impl Player {
         self.gold += amount
     }
 
+    pub fn remove_gold(&mut self, amount: u64) {
+        self.gold = self.gold.saturating_sub(amount)
+    }
+
     pub fn dead(&self) -> bool {
         self.damage > self.hp()
     }
@@ -84,6 +379,11 @@ impl Player {
         let total = self.gold;
         self.inventory_items(world)
             .into_iter()
+            .chain(
+                self.equipment
+                    .keys()
+                    .filter_map(|slot| self.equipped(world, *slot)),
+            )
             .fold(total, |total, item| total + item.value())
     }
 }
@@ -94,7 +394,13 @@ impl Default for Player {
             xp: 0,
             damage: 0,
             gold: 0,
-            inventory: HashMap::new(),
+            inventory: BTreeMap::new(),
+            equipment: BTreeMap::new(),
+            perception: BASE_PERCEPTION,
+            effects: BTreeMap::new(),
+            defending: false,
+            class: Class::default(),
+            abilities: Vec::new(),
         }
     }
 }
@@ -104,8 +410,16 @@ impl Level for Player {
         self.xp
     }
 
-    fn add_xp(&mut self, amount: u64) {
-        self.xp += amount
+    fn mut_xp(&mut self) -> &mut u64 {
+        &mut self.xp
+    }
+
+    fn hp(&self) -> u64 {
+        let multiplier = self
+            .abilities
+            .iter()
+            .fold(self.class.hp, |multiplier, ability| multiplier * ability.hp);
+        (((BASE_HP + self.level() * 5) as f64 * multiplier).round() as u64).max(1)
     }
 }
 
@@ -117,4 +431,133 @@ impl Inventory for Player {
     fn mut_inventory(&mut self) -> &mut RawInventory {
         &mut self.inventory
     }
+
+    /// Only equipped armor counts towards the player's defense
+    fn defense(&self, world: &World) -> u64 {
+        self.equipment
+            .keys()
+            .filter_map(|slot| match self.equipped(world, *slot)?.kind() {
+                ItemKind::Armor { defense, .. } => Some(defense),
+                _ => None,
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::interface::GameRng;
+
+    #[test]
+    fn classes_start_with_their_items_and_abilities() {
+        let mut world = World::new();
+        world
+            .load_items_from(
+                r#"[
+                    { "id": "shiv", "name": "Shiv", "kind": "weapon", "damage": 5, "value": 5 },
+                    {
+                        "id": "hide-armor",
+                        "name": "Hide Armor",
+                        "kind": "armor",
+                        "limb": "body",
+                        "defense": 20,
+                        "value": 50
+                    }
+                ]"#
+                .as_bytes(),
+            )
+            .unwrap();
+        world
+            .load_abilities_from(r#"[{ "id": "tough", "hp": 2.0 }]"#.as_bytes())
+            .unwrap();
+        let class: Class = serde_json::from_str(
+            r#"{
+                "id": "brute",
+                "name": "Brute",
+                "items": { "shiv": 1, "hide-armor": 1, "missing": 2 },
+                "hp": 1.5,
+                "abilities": ["tough", "missing"]
+            }"#,
+        )
+        .unwrap();
+
+        let player = Player::new(&class, &world);
+        assert_eq!(player.class().id, "brute");
+        assert_eq!(
+            player.weapon(&world).map(|item| item.id().as_str()),
+            Some("shiv")
+        );
+        assert_eq!(
+            player
+                .equipped(&world, Slot::Body)
+                .map(|item| item.id().as_str()),
+            Some("hide-armor")
+        );
+        assert_eq!(player.has_item("missing"), None);
+        let abilities: Vec<&str> = player
+            .abilities()
+            .iter()
+            .map(|ability| ability.id.as_str())
+            .collect();
+        assert_eq!(abilities, vec!["tough"]);
+        assert_eq!(player.hp(), 60);
+    }
+
+    #[test]
+    fn weapons_without_damage_still_hit() {
+        let mut world = World::new();
+        world
+            .load_items_from(
+                r#"[{ "id": "twig", "name": "Twig", "kind": "weapon", "damage": 0, "value": 0 }]"#
+                    .as_bytes(),
+            )
+            .unwrap();
+        let mut player = Player::default();
+        player.add_item("twig");
+        player.auto_equip(&world);
+        let mut rng = GameRng::seed_from_u64(0);
+        assert_eq!(player.attack_damage(&world, &mut rng), 1);
+    }
+
+    #[test]
+    fn round_effects_tick_until_they_wear_off() {
+        let mut player = Player::default();
+        player.afflict(Effect::Bleeding, 2);
+        player.afflict(Effect::Poison, 2);
+        let damage = (player.hp() as f64 * Effect::Bleeding.damage()).ceil() as u64;
+
+        let ticked = player.tick_effects(Tick::Round);
+        assert_eq!(ticked.len(), 1);
+        assert!(matches!(ticked[0], (Effect::Bleeding, d, false) if d == damage));
+
+        let ticked = player.tick_effects(Tick::Round);
+        assert!(matches!(ticked[0], (Effect::Bleeding, d, true) if d == damage));
+        assert_eq!(player.hp_remaining(), player.hp() - damage * 2);
+        assert!(!player.effects().contains_key(&Effect::Bleeding));
+        assert_eq!(player.effects().get(&Effect::Poison), Some(&2));
+    }
+
+    #[test]
+    fn cave_effects_never_kill() {
+        let mut player = Player::default();
+        player.afflict(Effect::Poison, 100);
+        for _ in 0..100 {
+            player.tick_effects(Tick::Cave);
+        }
+        assert_eq!(player.hp_remaining(), 1);
+        assert!(player.effects().is_empty());
+    }
+
+    #[test]
+    fn afflicting_again_keeps_the_longer_duration() {
+        let mut player = Player::default();
+        player.afflict(Effect::Burning, 3);
+        player.afflict(Effect::Burning, 1);
+        assert_eq!(player.effects().get(&Effect::Burning), Some(&3));
+        player.afflict(Effect::Burning, 5);
+        assert_eq!(player.effects().get(&Effect::Burning), Some(&5));
+    }
 }
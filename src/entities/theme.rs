@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use super::item::ItemKind;
+
+/// How much more or less likely each kind of item is to turn up as loot, as a
+/// multiple of the usual chance.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LootBias {
+    #[serde(default = "LootBias::unbiased")]
+    pub weapon: f64,
+    #[serde(default = "LootBias::unbiased")]
+    pub armor: f64,
+    #[serde(default = "LootBias::unbiased")]
+    pub potion: f64,
+    #[serde(default = "LootBias::unbiased")]
+    pub collectible: f64,
+}
+
+impl LootBias {
+    fn unbiased() -> f64 {
+        1.0
+    }
+
+    pub fn weight(&self, kind: &ItemKind) -> f64 {
+        match kind {
+            ItemKind::Weapon { .. } => self.weapon,
+            ItemKind::Armor { .. } => self.armor,
+            ItemKind::Potion { .. } => self.potion,
+            ItemKind::Collectible {} => self.collectible,
+        }
+    }
+}
+
+impl Default for LootBias {
+    fn default() -> Self {
+        Self {
+            weapon: 1.0,
+            armor: 1.0,
+            potion: 1.0,
+            collectible: 1.0,
+        }
+    }
+}
+
+/// A kind of cave, picked before anything in it is generated.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CaveTheme {
+    pub id: String,
+    pub name: String,
+    pub biome: String,
+    /// Monsters with any of these tags are more likely to live here
+    #[serde(default)]
+    pub monster_tags: Vec<String>,
+    #[serde(default)]
+    pub loot_bias: LootBias,
+}
+
+impl CaveTheme {
+    /// How much more likely a monster with a preferred tag is to be picked
+    pub const PREFERRED_MONSTER_WEIGHT: f64 = 5.0;
+
+    /// Weight for picking a monster with `tags` in a cave of this theme.
+    pub fn monster_weight(&self, tags: &[String]) -> f64 {
+        if tags.iter().any(|tag| self.monster_tags.contains(tag)) {
+            Self::PREFERRED_MONSTER_WEIGHT
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for CaveTheme {
+    /// Used when no themes are loaded at all.
+    fn default() -> Self {
+        Self {
+            id: "cave".into(),
+            name: "The Cave".into(),
+            biome: "cavern".into(),
+            monster_tags: Vec::new(),
+            loot_bias: LootBias::default(),
+        }
+    }
+}
@@ -0,0 +1,637 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::locale::{self, Plural, FALLBACK_LOCALE};
+
+use super::{
+    ability::Ability,
+    boss::Boss,
+    class::Class,
+    item::{Item, ItemKind},
+    loot::LootTable,
+    monster::PotentialMonster,
+    theme::CaveTheme,
+    World,
+};
+
+/// A problem found in a content or localization file.
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Finds the line the `nth` entry with `id` is declared on.
+fn line_of(text: &str, id: &str, nth: usize) -> Option<usize> {
+    let id = format!("\"{}\"", id);
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains("\"id\"") && line.contains(&id))
+        .nth(nth)
+        .map(|(index, _)| index + 1)
+}
+
+/// Finds the line `reference` is first mentioned on, starting from the line
+/// the entry mentioning it is declared on.
+fn line_of_reference(file: &Path, from: Option<usize>, reference: &str) -> Option<usize> {
+    let text = fs::read_to_string(file).ok()?;
+    let reference = format!("\"{}\"", reference);
+    text.lines()
+        .enumerate()
+        .skip(from.map_or(0, |line| line - 1))
+        .find(|(_, line)| line.contains(&reference))
+        .map(|(index, _)| index + 1)
+        .or(from)
+}
+
+/// Ids of every entry in `files`, valid or not, for checking references
+/// against.
+fn ids_in(files: &[PathBuf]) -> HashSet<String> {
+    files
+        .iter()
+        .filter_map(|file| fs::read_to_string(file).ok())
+        .filter_map(|text| serde_json::from_str::<Vec<Value>>(&text).ok())
+        .flatten()
+        .filter_map(|entry| entry.get("id")?.as_str().map(String::from))
+        .collect()
+}
+
+/// Reports each of `references` to a `kind` of content that isn't `known`, on
+/// the line it's mentioned on.
+fn check_references<'a, I, F>(
+    file: &Path,
+    line: Option<usize>,
+    entry: &str,
+    kind: &str,
+    references: I,
+    known: F,
+) -> Vec<Diagnostic>
+where
+    I: IntoIterator<Item = &'a String>,
+    F: Fn(&str) -> bool,
+{
+    references
+        .into_iter()
+        .filter(|reference| !known(reference))
+        .map(|reference| Diagnostic {
+            file: file.to_path_buf(),
+            line: line_of_reference(file, line, reference),
+            message: format!("{}: unknown {} `{}`", entry, kind, reference),
+        })
+        .collect()
+}
+
+/// Reads a file as a JSON array, reporting anything that gets in the way.
+fn read_entries(file: &Path, diagnostics: &mut Vec<Diagnostic>) -> Option<(String, Vec<Value>)> {
+    let text = match fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(error) => {
+            diagnostics.push(Diagnostic {
+                file: file.to_path_buf(),
+                line: None,
+                message: error.to_string(),
+            });
+            return None;
+        }
+    };
+    match serde_json::from_str(&text) {
+        Ok(entries) => Some((text, entries)),
+        Err(error) => {
+            diagnostics.push(Diagnostic {
+                file: file.to_path_buf(),
+                line: Some(error.line()),
+                message: error.to_string(),
+            });
+            None
+        }
+    }
+}
+
+/// Strictly deserializes every entry of every file and reports duplicate ids
+/// across files. Returns the entries that could be read.
+fn read_all<T, F>(
+    files: &[PathBuf],
+    id: F,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<(PathBuf, Option<usize>, T)>
+where
+    T: for<'de> Deserialize<'de>,
+    F: Fn(&T) -> &str,
+{
+    let mut seen: HashMap<String, (PathBuf, Option<usize>)> = HashMap::new();
+    let mut all = Vec::new();
+
+    for file in files {
+        let (text, entries) = match read_entries(file, diagnostics) {
+            Some(entries) => entries,
+            None => continue,
+        };
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        for entry in entries {
+            let line = entry.get("id").and_then(|id| id.as_str()).and_then(|id| {
+                let nth = occurrences.entry(id.to_string()).or_default();
+                *nth += 1;
+                line_of(&text, id, *nth - 1)
+            });
+            let entry = match T::deserialize(entry) {
+                Ok(entry) => entry,
+                Err(error) => {
+                    diagnostics.push(Diagnostic {
+                        file: file.clone(),
+                        line,
+                        message: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            if let Some((first_file, first_line)) = seen.get(id(&entry)) {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!(
+                        "duplicate id `{}`, first defined at {}{}",
+                        id(&entry),
+                        first_file.display(),
+                        first_line
+                            .map(|line| format!(":{}", line))
+                            .unwrap_or_default()
+                    ),
+                });
+            } else {
+                seen.insert(id(&entry).to_string(), (file.clone(), line));
+            }
+            all.push((file.clone(), line, entry));
+        }
+    }
+
+    all
+}
+
+impl World {
+    /// Checks content and localization files for mistakes the loaders would
+    /// silently accept. References to other content may point into the files
+    /// themselves or into what's already loaded into the world, e.g. from
+    /// earlier packs. Returns every problem found, or nothing if the files
+    /// are fine.
+    #[allow(clippy::too_many_arguments)]
+    pub fn validate(
+        &self,
+        item_files: &[PathBuf],
+        monster_files: &[PathBuf],
+        ability_files: &[PathBuf],
+        boss_files: &[PathBuf],
+        class_files: &[PathBuf],
+        theme_files: &[PathBuf],
+        loot_files: &[PathBuf],
+        locale_files: &[PathBuf],
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let item_ids = ids_in(item_files);
+        let known_item = |id: &str| item_ids.contains(id) || self.get_item(id).is_some();
+        let monster_ids = ids_in(monster_files);
+        let known_monster = |id: &str| monster_ids.contains(id) || self.get_monster(id).is_some();
+        let ability_ids = ids_in(ability_files);
+        let known_ability =
+            |id: &str| ability_ids.contains(id) || self.abilities().contains_key(id);
+
+        // Items
+        for (file, line, item) in read_all(item_files, |item: &Item| item.id(), &mut diagnostics) {
+            let mut report = |message: &str| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("item `{}`: {}", item.id(), message),
+                })
+            };
+            if item.id().is_empty() {
+                report("id is empty");
+            }
+            if item.raw_name().is_empty() {
+                report("name is empty");
+            }
+            if item
+                .grants()
+                .iter()
+                .any(|affliction| affliction.duration == 0)
+            {
+                report("granted effect durations must be above 0");
+            }
+            match item.kind() {
+                ItemKind::Weapon { damage: 0 } => report("weapon damage must be above 0"),
+                ItemKind::Potion { hp: 0 } => report("potion hp must be above 0"),
+                _ => {}
+            }
+        }
+
+        // Monsters
+        let monsters = read_all(
+            monster_files,
+            |monster: &PotentialMonster| &monster.id,
+            &mut diagnostics,
+        );
+        for (file, line, monster) in monsters {
+            let mut report = |message: String| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("monster `{}`: {}", monster.id, message),
+                })
+            };
+            if monster.id.is_empty() {
+                report("id is empty".to_string());
+            }
+            if monster.name.is_empty() {
+                report("name is empty".to_string());
+            }
+            for drop in &monster.drops {
+                match (&drop.item, &drop.tag) {
+                    (Some(_), Some(_)) => {
+                        report("drops need an item or a tag, not both".to_string())
+                    }
+                    (None, None) => report("drops need an item or a tag".to_string()),
+                    _ => {}
+                }
+                if !(0.0..=1.0).contains(&drop.chance) {
+                    report(format!(
+                        "drop chance {} is not between 0 and 1",
+                        drop.chance
+                    ));
+                }
+                if drop.count.min > drop.count.max {
+                    report("drop count min is above max".to_string());
+                }
+            }
+            for inflict in &monster.inflicts {
+                if !(0.0..=1.0).contains(&inflict.chance) {
+                    report(format!(
+                        "{} chance {} is not between 0 and 1",
+                        inflict.effect.id(),
+                        inflict.chance
+                    ));
+                }
+                if inflict.duration == 0 {
+                    report(format!("{} duration must be above 0", inflict.effect.id()));
+                }
+            }
+            match (monster.max_level, monster.rarity) {
+                (Some(0), _) => report("max_level must be above 0".to_string()),
+                (Some(max_level), Some(rarity)) if max_level < rarity.level_range().start => {
+                    report(format!(
+                        "max_level {} is below the minimum level of its rarity ({})",
+                        max_level,
+                        rarity.level_range().start
+                    ))
+                }
+                _ => {}
+            }
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &format!("monster `{}`", monster.id),
+                "item",
+                monster.drops.iter().filter_map(|drop| drop.item.as_ref()),
+                known_item,
+            ));
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &format!("monster `{}`", monster.id),
+                "ability",
+                &monster.abilities,
+                known_ability,
+            ));
+        }
+
+        // Abilities
+        let abilities = read_all(
+            ability_files,
+            |ability: &Ability| &ability.id,
+            &mut diagnostics,
+        );
+        for (file, line, ability) in abilities {
+            let mut report = |message: &str| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("ability `{}`: {}", ability.id, message),
+                })
+            };
+            if ability.id.is_empty() {
+                report("id is empty");
+            }
+            if ability.hp <= 0.0 || ability.damage <= 0.0 {
+                report("hp and damage multipliers must be above 0");
+            }
+            let chances = [
+                ("defense", ability.defense),
+                ("evasion", ability.evasion),
+                ("regeneration", ability.regeneration),
+                ("summons", ability.summons),
+                ("flees_below", ability.flees_below),
+            ];
+            for (name, chance) in chances.iter() {
+                if !(0.0..=1.0).contains(chance) {
+                    report(&format!("{} must be between 0 and 1", name));
+                }
+            }
+            if ability.defense >= 1.0 || ability.evasion >= 1.0 {
+                report("defense and evasion must be below 1, or the monster can't be beaten");
+            }
+        }
+
+        // Bosses
+        for (file, line, boss) in read_all(boss_files, |boss: &Boss| &boss.id, &mut diagnostics) {
+            let mut report = |message: &str| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("boss `{}`: {}", boss.id, message),
+                })
+            };
+            if boss.id.is_empty() {
+                report("id is empty");
+            }
+            if boss.hp <= 0.0 {
+                report("hp multiplier must be above 0");
+            }
+            if boss.phases.iter().any(|phase| phase.id.is_empty()) {
+                report("phase id is empty");
+            }
+            if boss
+                .phases
+                .iter()
+                .any(|phase| phase.below <= 0.0 || phase.below >= 1.0)
+            {
+                report("phase `below` must be between 0 and 1");
+            }
+            if boss
+                .phases
+                .windows(2)
+                .any(|phases| phases[1].below >= phases[0].below)
+            {
+                report("phases must be listed from the highest `below` down");
+            }
+            // Bosses are named after the monster they turn into a boss
+            let name = format!("boss `{}`", boss.id);
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &name,
+                "monster",
+                std::iter::once(&boss.id),
+                known_monster,
+            ));
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &name,
+                "ability",
+                boss.phases.iter().flat_map(|phase| &phase.abilities),
+                known_ability,
+            ));
+        }
+
+        // Classes
+        for (file, line, class) in
+            read_all(class_files, |class: &Class| &class.id, &mut diagnostics)
+        {
+            let mut report = |message: &str| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("class `{}`: {}", class.id, message),
+                })
+            };
+            if class.id.is_empty() {
+                report("id is empty");
+            }
+            if class.name.is_empty() {
+                report("name is empty");
+            }
+            if class.hp <= 0.0 || class.defense <= 0.0 || class.initiative <= 0.0 {
+                report("multipliers must be above 0");
+            }
+            if class.items.values().any(|count| *count == 0) {
+                report("item counts must be above 0");
+            }
+            let name = format!("class `{}`", class.id);
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &name,
+                "item",
+                class.items.keys(),
+                known_item,
+            ));
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &name,
+                "ability",
+                &class.abilities,
+                known_ability,
+            ));
+        }
+
+        // Cave themes
+        let themes = read_all(theme_files, |theme: &CaveTheme| &theme.id, &mut diagnostics);
+        for (file, line, theme) in themes {
+            let mut report = |message: &str| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("cave theme `{}`: {}", theme.id, message),
+                })
+            };
+            if theme.id.is_empty() {
+                report("id is empty");
+            }
+            if theme.name.is_empty() {
+                report("name is empty");
+            }
+            if theme.biome.is_empty() {
+                report("biome is empty");
+            }
+            let bias = &theme.loot_bias;
+            let weights = [bias.weapon, bias.armor, bias.potion, bias.collectible];
+            if weights
+                .iter()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+            {
+                report("loot_bias weights must be 0 or above");
+            } else if weights.iter().all(|weight| *weight == 0.0) {
+                report("loot_bias can't rule out every kind of item");
+            }
+        }
+
+        // Loot tables
+        let tables = read_all(loot_files, |table: &LootTable| &table.id, &mut diagnostics);
+        for (file, line, table) in tables {
+            let mut report = |message: &str| {
+                diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message: format!("loot table `{}`: {}", table.id, message),
+                })
+            };
+            if table.id.is_empty() {
+                report("id is empty");
+            }
+            if table.rolls.min > table.rolls.max {
+                report("rolls min is above max");
+            }
+            if table
+                .weights
+                .values()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+            {
+                report("weights must be 0 or above");
+            }
+            if table
+                .level_scaling
+                .values()
+                .any(|scaling| !scaling.is_finite())
+            {
+                report("level_scaling must be a number");
+            }
+            if table
+                .level_scaling
+                .keys()
+                .any(|rarity| !table.weights.contains_key(rarity))
+            {
+                report("level_scaling has a rarity without a weight");
+            }
+            for drop in &table.guaranteed {
+                match (&drop.item, drop.rarity) {
+                    (Some(_), Some(_)) => {
+                        report("guaranteed drops need an item or a rarity, not both")
+                    }
+                    (None, None) => report("guaranteed drops need an item or a rarity"),
+                    _ => {}
+                }
+                if drop.count == 0 {
+                    report("guaranteed drop count must be above 0");
+                }
+            }
+            diagnostics.extend(check_references(
+                &file,
+                line,
+                &format!("loot table `{}`", table.id),
+                "item",
+                table
+                    .guaranteed
+                    .iter()
+                    .filter_map(|drop| drop.item.as_ref()),
+                known_item,
+            ));
+        }
+
+        // Localization
+        let mut locales: BTreeMap<&PathBuf, HashMap<String, Vec<String>>> = BTreeMap::new();
+        for file in locale_files {
+            let parsed = fs::read_to_string(file)
+                .map_err(|error| (None, error.to_string()))
+                .and_then(|text| {
+                    serde_json::from_str(&text)
+                        .map_err(|error| (Some(error.line()), error.to_string()))
+                });
+            match parsed {
+                Ok(lines) => {
+                    locales.insert(file, lines);
+                }
+                Err((line, message)) => diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line,
+                    message,
+                }),
+            }
+        }
+        for (file, lines) in &locales {
+            if let Some(rule) = lines.get("plural.rule").and_then(|rule| rule.first()) {
+                if !Plural::RULES.contains(&rule.as_str()) {
+                    diagnostics.push(Diagnostic {
+                        file: (*file).clone(),
+                        line: None,
+                        message: format!(
+                            "unknown plural rule `{}` (expected one of {})",
+                            rule,
+                            Plural::RULES.join(", ")
+                        ),
+                    });
+                }
+            }
+        }
+        let reference = locales.iter().find(|(file, _)| {
+            file.file_stem()
+                .is_some_and(|stem| locale::normalize(&stem.to_string_lossy()) == FALLBACK_LOCALE)
+        });
+        if let Some((reference_file, reference)) = reference {
+            for (file, lines) in &locales {
+                // Names only need translating where they differ
+                let mut missing: Vec<&String> = reference
+                    .keys()
+                    .filter(|key| {
+                        !key.starts_with("monster.")
+                            && !key.starts_with("item.")
+                            && !key.starts_with("cave.")
+                            && !key.starts_with("class.")
+                    })
+                    .filter(|key| !lines.contains_key(*key))
+                    // Locales only need the plural forms their plural rule uses
+                    .filter(|key| match key.rsplit_once('.') {
+                        Some((base, category))
+                            if Plural::ALL.iter().any(|plural| plural.key() == category) =>
+                        {
+                            !Plural::ALL.iter().any(|plural| {
+                                lines.contains_key(&format!("{}.{}", base, plural.key()))
+                            })
+                        }
+                        _ => true,
+                    })
+                    .collect();
+                missing.sort();
+                for key in missing {
+                    diagnostics.push(Diagnostic {
+                        file: (*file).clone(),
+                        line: None,
+                        message: format!(
+                            "missing key `{}` (defined in {})",
+                            key,
+                            reference_file.display()
+                        ),
+                    });
+                }
+                let mut empty: Vec<&String> = lines
+                    .iter()
+                    .filter(|(_, variants)| variants.is_empty())
+                    .map(|(key, _)| key)
+                    .collect();
+                empty.sort();
+                for key in empty {
+                    diagnostics.push(Diagnostic {
+                        file: (*file).clone(),
+                        line: None,
+                        message: format!("key `{}` has no lines", key),
+                    });
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
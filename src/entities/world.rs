@@ -1,20 +1,31 @@
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 
 use super::{
+    ability::Ability,
+    boss::Boss,
+    class::Class,
     item::Item,
+    loot::{GuaranteedDrop, LootTable},
     monster::{Monster, PotentialMonster},
     player::Player,
+    theme::CaveTheme,
     Inventory, Level, Rarity, RawInventory,
 };
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Stats {
     pub caves: u64,
     pub monsters: u64,
+    /// Ids of the bosses defeated, in order
+    #[serde(default)]
+    pub bosses: Vec<String>,
 }
 
 impl Default for Stats {
@@ -22,6 +33,7 @@ impl Default for Stats {
         Self {
             caves: 0,
             monsters: 0,
+            bosses: Vec::new(),
         }
     }
 }
@@ -42,16 +54,186 @@ impl CaveDifficulty {
             false => Self::Hard,
         }
     }
+
+    /// Name used for the difficulty in locale keys.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Easy => "easy",
+            Self::Hard => "hard",
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Easy => Self::Hard,
+            Self::Hard => Self::Easy,
+        }
+    }
 }
 
-pub struct Cave {
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RoomKind {
+    Entrance,
+    Corridor,
+    Treasure,
+    /// Where the strongest monster in the cave waits, or a boss at the end
+    /// of a hard cave
+    Boss,
+}
+
+impl RoomKind {
+    /// Localization key the room's lines are found under.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Entrance => "rooms.entrance",
+            Self::Corridor => "rooms.corridor",
+            Self::Treasure => "rooms.treasure",
+            Self::Boss => "rooms.boss",
+        }
+    }
+}
+
+pub struct Room {
+    pub kind: RoomKind,
     pub loot: RawInventory,
     pub gold: u64,
     pub monsters: Vec<Monster>,
-    // TODO: name: String
+    /// Rooms reachable from this one, as indices into [`Cave::rooms`]
+    pub passages: Vec<usize>,
+    pub visited: bool,
+}
+
+impl Room {
+    fn new(kind: RoomKind) -> Self {
+        Self {
+            kind,
+            loot: RawInventory::new(),
+            gold: 0,
+            monsters: Vec::new(),
+            passages: Vec::new(),
+            visited: false,
+        }
+    }
 }
 
-impl Inventory for Cave {
+pub struct Cave {
+    /// Id of the [`CaveTheme`] the cave was generated from
+    pub theme: String,
+    /// The theme's name from the content files. Use
+    /// [`crate::locale::Lexicon::cave`] to display it.
+    pub name: String,
+    pub biome: String,
+    pub difficulty: CaveDifficulty,
+    /// Every room connects to one before it, so exploring them in order never
+    /// skips ahead of a passage
+    pub rooms: Vec<Room>,
+}
+
+impl Cave {
+    /// Index of the room every cave is entered and left through
+    pub const ENTRANCE: usize = 0;
+
+    /// Every monster in every room.
+    pub fn monsters(&self) -> impl Iterator<Item = &Monster> {
+        self.rooms.iter().flat_map(|room| room.monsters.iter())
+    }
+
+    /// Adds a room with a passage to and from `from`, returning its index.
+    fn add_room(&mut self, kind: RoomKind, from: usize) -> usize {
+        let index = self.rooms.len();
+        self.rooms.push(Room::new(kind));
+        self.rooms[index].passages.push(from);
+        self.rooms[from].passages.push(index);
+        index
+    }
+
+    /// Lays out a small graph of rooms and spreads the cave's contents over
+    /// them: the boss, or else the strongest monster, waits in the boss room
+    /// with the boss's loot, and the treasure room holds most of the rest.
+    fn generate<R>(
+        theme: &CaveTheme,
+        difficulty: CaveDifficulty,
+        loot: RawInventory,
+        gold: u64,
+        mut monsters: Vec<Monster>,
+        boss: Option<(Monster, RawInventory)>,
+        rng: &mut R,
+    ) -> Self
+    where
+        R: Rng,
+    {
+        let mut cave = Self {
+            theme: theme.id.clone(),
+            name: theme.name.clone(),
+            biome: theme.biome.clone(),
+            difficulty,
+            rooms: vec![Room::new(RoomKind::Entrance)],
+        };
+
+        let corridors: Vec<usize> = (0..rng.gen_range(1..=3))
+            .map(|_| {
+                let from = rng.gen_range(0..cave.rooms.len());
+                cave.add_room(RoomKind::Corridor, from)
+            })
+            .collect();
+        let pick_corridor = |rng: &mut R| {
+            *corridors
+                .choose(rng)
+                .expect("Caves have at least one corridor")
+        };
+        let treasure = cave.add_room(RoomKind::Treasure, pick_corridor(rng));
+
+        monsters.sort_by_key(|monster| monster.level());
+        let (boss, boss_loot) = match boss {
+            Some((monster, loot)) => (Some(monster), loot),
+            None => (monsters.pop(), RawInventory::new()),
+        };
+        let boss = boss.map(|monster| {
+            let boss = cave.add_room(RoomKind::Boss, pick_corridor(rng));
+            cave.rooms[boss].monsters.push(monster);
+            cave.rooms[boss].loot = boss_loot;
+            boss
+        });
+
+        // Everything but the entrance can hold the rest
+        let mut guarded = corridors.clone();
+        guarded.push(treasure);
+        for monster in monsters {
+            let room = *guarded
+                .choose(rng)
+                .expect("Caves have at least one corridor");
+            cave.rooms[room].monsters.push(monster);
+        }
+
+        let mut holds_loot = guarded;
+        holds_loot.extend(boss);
+        for (item, count) in loot {
+            for _ in 0..count {
+                let room = if rng.gen_bool(0.5) {
+                    treasure
+                } else {
+                    *holds_loot
+                        .choose(rng)
+                        .expect("Caves have at least one corridor")
+                };
+                cave.rooms[room].add_item(&item);
+            }
+        }
+
+        match boss {
+            Some(boss) => {
+                let boss_gold = gold / 3;
+                cave.rooms[boss].gold = boss_gold;
+                cave.rooms[treasure].gold = gold - boss_gold;
+            }
+            None => cave.rooms[treasure].gold = gold,
+        }
+
+        cave
+    }
+}
+
+impl Inventory for Room {
     fn inventory(&self) -> &RawInventory {
         &self.loot
     }
@@ -62,8 +244,13 @@ impl Inventory for Cave {
 }
 
 pub struct World {
-    items: HashMap<String, Item>,
-    monsters: HashMap<String, PotentialMonster>,
+    items: BTreeMap<String, Item>,
+    monsters: BTreeMap<String, PotentialMonster>,
+    themes: BTreeMap<String, CaveTheme>,
+    loot_tables: BTreeMap<String, LootTable>,
+    abilities: BTreeMap<String, Ability>,
+    bosses: BTreeMap<String, Boss>,
+    classes: BTreeMap<String, Class>,
     pub stats: Stats,
 }
 
@@ -77,7 +264,7 @@ impl World {
         self.stats = Stats::default();
     }
 
-    pub fn items(&self) -> &HashMap<String, Item> {
+    pub fn items(&self) -> &BTreeMap<String, Item> {
         &self.items
     }
 
@@ -89,16 +276,20 @@ impl World {
         self.items.get(item)
     }
 
+    /// Loads every item in `file`, replacing any already loaded with the same
+    /// id. Returns how many of them were new.
     pub fn load_items<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
-        let file = File::open(file)?;
-        let items: Vec<Item> = serde_json::from_reader(file)?;
+        self.load_items_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_items`], for item JSON that doesn't live on disk.
+    pub fn load_items_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let items: Vec<Item> = serde_json::from_reader(reader)?;
 
         Ok(items.into_iter().fold(0, |total, item| {
-            if !self.items.contains_key(item.id()) {
-                self.items.insert(item.id().into(), item);
-                total + 1
-            } else {
-                total
+            match self.items.insert(item.id().into(), item) {
+                Some(_) => total,
+                None => total + 1,
             }
         }))
     }
@@ -108,16 +299,21 @@ impl World {
         Ok(self)
     }
 
+    /// Loads every monster in `file`, replacing any already loaded with the
+    /// same id. Returns how many of them were new.
     pub fn load_monsters<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
-        let file = File::open(file)?;
-        let monsters: Vec<PotentialMonster> = serde_json::from_reader(file)?;
+        self.load_monsters_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_monsters`], for monster JSON that doesn't live on
+    /// disk.
+    pub fn load_monsters_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let monsters: Vec<PotentialMonster> = serde_json::from_reader(reader)?;
 
         Ok(monsters.into_iter().fold(0, |total, monster| {
-            if !self.monsters.contains_key(&monster.id) {
-                self.monsters.insert(monster.id.clone(), monster);
-                total + 1
-            } else {
-                total
+            match self.monsters.insert(monster.id.clone(), monster) {
+                Some(_) => total,
+                None => total + 1,
             }
         }))
     }
@@ -127,75 +323,325 @@ impl World {
         Ok(self)
     }
 
+    pub fn get_monster(&self, monster: &str) -> Option<&PotentialMonster> {
+        self.monsters.get(monster)
+    }
+
+    /// Rolls the drops of a defeated `monster`.
+    pub fn roll_drops<R>(&self, monster: &Monster, rng: &mut R) -> RawInventory
+    where
+        R: Rng,
+    {
+        let mut drops = RawInventory::new();
+        if let Some(potential) = self.get_monster(monster.id()) {
+            for drop in &potential.drops {
+                drop.roll(&self.items, &mut drops, rng);
+            }
+        }
+        drops
+    }
+
+    /// Spawns any generic monster up to `max_rarity`. Unique monsters only
+    /// turn up where caves put them.
+    pub fn spawn_monster<R>(&self, max_rarity: Rarity, rng: &mut R) -> Option<Monster>
+    where
+        R: Rng,
+    {
+        self.monsters
+            .values()
+            .filter(|monster| monster.generic)
+            .filter(|monster| monster.rarity.unwrap_or(Rarity::Petty) <= max_rarity)
+            .choose(rng)
+            .map(|monster| monster.spawn(max_rarity, &self.abilities, rng))
+    }
+
+    pub fn themes(&self) -> &BTreeMap<String, CaveTheme> {
+        &self.themes
+    }
+
+    /// Loads every cave theme in `file`, replacing any already loaded with the
+    /// same id. Returns how many of them were new.
+    pub fn load_themes<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
+        self.load_themes_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_themes`], for theme JSON that doesn't live on disk.
+    pub fn load_themes_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let themes: Vec<CaveTheme> = serde_json::from_reader(reader)?;
+
+        Ok(themes.into_iter().fold(0, |total, theme| {
+            match self.themes.insert(theme.id.clone(), theme) {
+                Some(_) => total,
+                None => total + 1,
+            }
+        }))
+    }
+
+    pub fn loot_tables(&self) -> &BTreeMap<String, LootTable> {
+        &self.loot_tables
+    }
+
+    /// Loads every loot table in `file`, replacing any already loaded with the
+    /// same id. Returns how many of them were new.
+    pub fn load_loot_tables<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
+        self.load_loot_tables_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_loot_tables`], for loot table JSON that doesn't live
+    /// on disk.
+    pub fn load_loot_tables_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let tables: Vec<LootTable> = serde_json::from_reader(reader)?;
+
+        Ok(tables.into_iter().fold(0, |total, table| {
+            match self.loot_tables.insert(table.id.clone(), table) {
+                Some(_) => total,
+                None => total + 1,
+            }
+        }))
+    }
+
+    pub fn abilities(&self) -> &BTreeMap<String, Ability> {
+        &self.abilities
+    }
+
+    /// Loads every monster ability in `file`, replacing any already loaded
+    /// with the same id. Returns how many of them were new.
+    pub fn load_abilities<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
+        self.load_abilities_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_abilities`], for ability JSON that doesn't live on
+    /// disk.
+    pub fn load_abilities_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let abilities: Vec<Ability> = serde_json::from_reader(reader)?;
+
+        Ok(abilities.into_iter().fold(0, |total, ability| {
+            match self.abilities.insert(ability.id.clone(), ability) {
+                Some(_) => total,
+                None => total + 1,
+            }
+        }))
+    }
+
+    pub fn bosses(&self) -> &BTreeMap<String, Boss> {
+        &self.bosses
+    }
+
+    pub fn get_boss(&self, boss: &str) -> Option<&Boss> {
+        self.bosses.get(boss)
+    }
+
+    /// Loads every boss in `file`, replacing any already loaded with the same
+    /// id. Returns how many of them were new.
+    pub fn load_bosses<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
+        self.load_bosses_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_bosses`], for boss JSON that doesn't live on disk.
+    pub fn load_bosses_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let bosses: Vec<Boss> = serde_json::from_reader(reader)?;
+
+        Ok(bosses.into_iter().fold(0, |total, boss| {
+            match self.bosses.insert(boss.id.clone(), boss) {
+                Some(_) => total,
+                None => total + 1,
+            }
+        }))
+    }
+
+    pub fn classes(&self) -> &BTreeMap<String, Class> {
+        &self.classes
+    }
+
+    pub fn get_class(&self, class: &str) -> Option<&Class> {
+        self.classes.get(class)
+    }
+
+    /// Loads every character class in `file`, replacing any already loaded
+    /// with the same id. Returns how many of them were new.
+    pub fn load_classes<P: AsRef<Path>>(&mut self, file: P) -> Result<u32, Box<dyn Error>> {
+        self.load_classes_from(File::open(file)?)
+    }
+
+    /// Like [`World::load_classes`], for class JSON that doesn't live on disk.
+    pub fn load_classes_from<R: Read>(&mut self, reader: R) -> Result<u32, Box<dyn Error>> {
+        let classes: Vec<Class> = serde_json::from_reader(reader)?;
+
+        Ok(classes.into_iter().fold(0, |total, class| {
+            match self.classes.insert(class.id.clone(), class) {
+                Some(_) => total,
+                None => total + 1,
+            }
+        }))
+    }
+
+    /// Spawns a boss that hasn't been defeated this run, along with the loot
+    /// it guards.
+    fn spawn_boss<R>(
+        &self,
+        player: &Player,
+        theme: &CaveTheme,
+        rng: &mut R,
+    ) -> Option<(Monster, RawInventory)>
+    where
+        R: Rng,
+    {
+        let (boss, monster) = self
+            .bosses
+            .values()
+            .filter(|boss| !self.stats.bosses.contains(&boss.id))
+            .filter_map(|boss| self.monsters.get(&boss.id).map(|monster| (boss, monster)))
+            .choose(rng)?;
+
+        let default_table = LootTable {
+            guaranteed: vec![GuaranteedDrop {
+                item: None,
+                rarity: Some(Rarity::Rare),
+                count: 1,
+            }],
+            ..LootTable::default()
+        };
+        let mut loot = RawInventory::new();
+        self.loot_tables
+            .get(LootTable::BOSS)
+            .unwrap_or(&default_table)
+            .roll(
+                &self.items,
+                player.level(),
+                &theme.loot_bias,
+                &mut loot,
+                rng,
+            );
+
+        Some((boss.spawn(monster, &self.abilities, rng), loot))
+    }
+
     pub fn new_cave<R>(&mut self, player: &Player, rng: &mut R, difficulty: CaveDifficulty) -> Cave
     where
         R: Rng,
     {
-        // Generate loot
-        let loot_count = rng.gen_range(1..5);
-        let loot = self.item_ids();
-        // TODO: Consider whether loot rarity scaling is more fun/rewarding
-        let loot =
-            loot.choose_multiple(rng, loot_count)
-                .fold(HashMap::new(), |mut loot_map, item| {
-                    if let Some(item_count) = loot_map.get_mut(item) {
-                        *item_count += 1
-                    } else {
-                        loot_map.insert(item.clone(), 1);
-                    }
-                    loot_map
-                });
+        // The theme decides what the cave holds, so pick it first
+        let default_theme = CaveTheme::default();
+        let theme = self.themes.values().choose(rng).unwrap_or(&default_theme);
 
         // Generate gold
         let gold = rng.gen_range(0..200);
 
-        // Generate monsters
+        // Generate monsters. Packs can leave none that fit, e.g. when every
+        // monster is a boss, and then the cave is empty
         let mut monsters = Vec::new();
 
         let player_max_rarity = Rarity::from_level(player.level());
 
         if difficulty == CaveDifficulty::Easy {
             let monster_count = (rng.gen_range(0.0..500_f64).sqrt() / 10.0) as u32;
-            let monster_options = self
+            let monster_options: Vec<&PotentialMonster> = self
                 .monsters
                 .values()
-                .filter(|monster| monster.rarity.unwrap_or(Rarity::Petty) <= player_max_rarity);
+                .filter(|monster| !self.bosses.contains_key(&monster.id))
+                .filter(|monster| monster.rarity.unwrap_or(Rarity::Petty) <= player_max_rarity)
+                .collect();
             for _ in 0..monster_count {
-                monsters.push(
-                    monster_options
-                        .clone()
-                        .choose(rng)
-                        .expect("Monsters will not be empty.")
-                        .spawn(player_max_rarity, rng),
-                );
+                if let Ok(monster) = monster_options
+                    .choose_weighted(rng, |monster| theme.monster_weight(&monster.tags))
+                {
+                    monsters.push(monster.spawn(player_max_rarity, &self.abilities, rng));
+                }
             }
         } else {
             let monster_count = (rng.gen_range(0.0..1000_f64).sqrt() / 10.0) as u32;
+            let monster_options: Vec<&PotentialMonster> = self
+                .monsters
+                .values()
+                .filter(|monster| !self.bosses.contains_key(&monster.id))
+                .collect();
             for _ in 0..monster_count {
-                monsters.push(
-                    self.monsters
-                        .values()
-                        .choose(rng)
-                        .expect("Monsters will not be empty.")
-                        .spawn(Rarity::Legendary, rng),
-                );
+                if let Ok(monster) = monster_options
+                    .choose_weighted(rng, |monster| theme.monster_weight(&monster.tags))
+                {
+                    monsters.push(monster.spawn(Rarity::Legendary, &self.abilities, rng));
+                }
             }
         }
 
-        Cave {
-            loot,
-            gold,
-            monsters,
+        // Generate loot
+        let default_table = LootTable::default();
+        let table = match difficulty {
+            CaveDifficulty::Easy => LootTable::EASY_CAVE,
+            CaveDifficulty::Hard => LootTable::HARD_CAVE,
+        };
+        let mut loot = RawInventory::new();
+        self.loot_tables.get(table).unwrap_or(&default_table).roll(
+            &self.items,
+            player.level(),
+            &theme.loot_bias,
+            &mut loot,
+            rng,
+        );
+        // Unique monsters guard something worth the trouble
+        if let Some(table) = self.loot_tables.get(LootTable::UNIQUE_MONSTER) {
+            for _ in monsters.iter().filter(|monster| !monster.generic()) {
+                table.roll(
+                    &self.items,
+                    player.level(),
+                    &theme.loot_bias,
+                    &mut loot,
+                    rng,
+                );
+            }
         }
+
+        // Bosses only wait at the end of hard caves, and not every time
+        let boss = match difficulty {
+            CaveDifficulty::Hard if rng.gen_bool(Boss::CHANCE) => {
+                self.spawn_boss(player, theme, rng)
+            }
+            _ => None,
+        };
+
+        Cave::generate(theme, difficulty, loot, gold, monsters, boss, rng)
     }
 }
 
 impl Default for World {
     fn default() -> Self {
         World {
-            items: HashMap::new(),
-            monsters: HashMap::new(),
+            items: BTreeMap::new(),
+            monsters: BTreeMap::new(),
+            themes: BTreeMap::new(),
+            loot_tables: BTreeMap::new(),
+            abilities: BTreeMap::new(),
+            bosses: BTreeMap::new(),
+            classes: BTreeMap::new(),
             stats: Stats::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::GameRng;
+
+    #[test]
+    fn caves_are_empty_when_every_monster_is_a_boss() {
+        let mut world = World::new();
+        world
+            .load_monsters_from(
+                r#"[{ "id": "ogre", "name": "Ogre", "generic": false, "rarity": "petty" }]"#
+                    .as_bytes(),
+            )
+            .unwrap();
+        world
+            .load_bosses_from(r#"[{ "id": "ogre" }]"#.as_bytes())
+            .unwrap();
+        let player = Player::default();
+        let mut rng = GameRng::seed_from_u64(0);
+        for _ in 0..20 {
+            for difficulty in [CaveDifficulty::Easy, CaveDifficulty::Hard] {
+                let cave = world.new_cave(&player, &mut rng, difficulty);
+                assert!(cave.monsters().all(|monster| monster.boss()));
+            }
+        }
+    }
+}
@@ -1,23 +1,23 @@
-use std::collections::HashMap;
+use std::path::Path;
 
 use colored::*;
-use hottext::{fmt_line, get_line, get_lines};
-use rand::prelude::*;
+use console::Term;
 
-use super::entities::{player::Player, CaveDifficulty, World, *};
+use super::engine::{self, Action, CaveResult, CaveReward, Clue, GameEvent, Renderer, RoomResult};
+use super::entities::{
+    class::Class,
+    effect::{Effect, Tick},
+    item::{Item, ItemKind, Slot},
+    merchant::Merchant,
+    monster::Monster,
+    player::Player,
+    World, *,
+};
 use super::interface::*;
+use super::leaderboard::{self, Leaderboard, Metric, Run};
+use super::save::SaveFile;
 use crate::colors;
-
-pub struct CaveReward {
-    pub xp: u64,
-    pub gold: u64,
-    pub loot: HashMap<String, u32>,
-}
-
-pub enum CaveResult {
-    Survived { reward: CaveReward },
-    Died,
-}
+use crate::locale::{fmt_line, get_line, Lexicon};
 
 pub enum CaveSurvivedChoice {
     ShowStatusReport,
@@ -53,175 +53,609 @@ impl GameOverChoice {
     }
 }
 
-pub fn enter_cave(world: &mut World, player: &mut Player, ctx: &mut Context) -> CaveResult {
-    spacer(ctx);
+/// What using `item` did, as lines to show the player.
+fn item_use_lines(
+    lexicon: &mut Lexicon,
+    item: &Item,
+    cured: &[Effect],
+    granted: &[Effect],
+) -> String {
+    let name = lexicon.item(item).name;
+    let mut lines = vec![match item.kind() {
+        ItemKind::Potion { .. } => fmt_line!(lexicon, "potion.use", potion = name.as_str()),
+        _ => fmt_line!(lexicon, "inventory.used", item = name.as_str()),
+    }];
+    for effect in cured {
+        lines.push(get_line!(
+            lexicon,
+            &format!("effects.{}.cured", effect.id())
+        ));
+    }
+    for effect in granted {
+        lines.push(get_line!(
+            lexicon,
+            &format!("effects.{}.granted", effect.id())
+        ));
+    }
+    lines.join("\n")
+}
 
-    // Cave
-    let harder_cave_difficulty = CaveDifficulty::random(&mut ctx.rng);
-    let mut caves = vec![
-        world.new_cave(player, &mut ctx.rng, CaveDifficulty::Easy),
-        world.new_cave(player, &mut ctx.rng, harder_cave_difficulty),
-    ];
-    caves.shuffle(&mut ctx.rng); // Ensure random spacial distribution of hard caves
-    let prompt = get_line!(ctx.hottext, "caves.approach");
-    let cave_names = get_lines!(ctx.hottext, "caves.names")
-        .into_iter()
-        .choose_multiple(&mut ctx.rng, 2);
-    let choice = get_choice(
-        ctx,
-        &prompt,
-        &cave_names
-            .iter()
-            .map(|s| s.as_str())
-            .take(2)
-            .collect::<Vec<&str>>(),
-    );
-    let cave = caves.remove(choice);
+/// An entry in the combat menu.
+enum CombatChoice {
+    Attack,
+    Defend,
+    Drink,
+    Use,
+    Flee,
+    AutoBattle,
+}
 
-    spacer(ctx);
+/// Draws game events to the terminal using the localized lines.
+pub struct ConsoleRenderer<'a> {
+    pub lexicon: &'a mut Lexicon,
+    pub term: &'a Term,
+    /// Turned on from the combat menu, and toggled from the menu between caves
+    pub auto_battle: &'a mut bool,
+}
 
-    ctx.term
-        .write_line(&fmt_line!(
-            ctx.hottext,
-            "caves.enter",
-            cave = cave_names[choice].as_str()
-        ))
-        .unwrap();
+impl<'a> ConsoleRenderer<'a> {
+    /// Borrows the game RNG alongside a renderer for the terminal.
+    pub fn split(ctx: &'a mut Context) -> (&'a mut GameRng, Self) {
+        (
+            &mut ctx.rng,
+            Self {
+                lexicon: &mut ctx.lexicon,
+                term: &ctx.term,
+                auto_battle: &mut ctx.auto_battle,
+            },
+        )
+    }
 
-    // Increment stats
-    world.stats.caves += 1;
-    world.stats.monsters += cave.monsters.len() as u64;
+    fn monster_line(&mut self, key: &str, monster: &Monster) -> String {
+        let enemy = self.lexicon.monster(monster);
+        fmt_line!(
+            self.lexicon,
+            &self.lexicon.gendered_key(key, &enemy.gender),
+            enemy = enemy.name.as_str(),
+            enemy_plural = enemy.plural.as_str(),
+            enemy_article = enemy.indefinite.as_str(),
+            enemy_proper = enemy.definite.as_str()
+        )
+    }
 
-    let mut xp = 500; // Minimum cave XP
+    /// The line for `monster`'s `ability` doing `trigger`, falling back to a
+    /// line shared by every ability. Abilities don't need a line for
+    /// everything, so this can be `None`.
+    fn ability_line(
+        &mut self,
+        monster: &Monster,
+        ability: &str,
+        trigger: &str,
+        hp: u64,
+        summoned: Option<&Monster>,
+    ) -> Option<String> {
+        let key = vec![
+            format!("abilities.{}.{}", ability, trigger),
+            format!("abilities.{}", trigger),
+        ]
+        .into_iter()
+        .find(|key| self.lexicon.has(key))?;
+        let enemy = self.lexicon.monster(monster);
+        let summoned = summoned
+            .map(|summoned| self.lexicon.monster(summoned).indefinite)
+            .unwrap_or_default();
+        let hp = self.lexicon.number(hp);
+        Some(fmt_line!(
+            self.lexicon,
+            &self.lexicon.gendered_key(&key, &enemy.gender),
+            enemy = enemy.name.as_str(),
+            enemy_plural = enemy.plural.as_str(),
+            enemy_article = enemy.indefinite.as_str(),
+            enemy_proper = enemy.definite.as_str(),
+            hp = hp.as_str(),
+            summoned = summoned.as_str()
+        ))
+    }
 
-    if cave.monsters.is_empty() {
-        ctx.term
-            .write_line(&get_line!(ctx.hottext, "combat.no-enemies"))
-            .unwrap();
-    } else {
-        for monster in cave.monsters {
-            spacer(ctx);
+    /// The boss's own `line`, or the line shared by every boss.
+    fn boss_line(&mut self, monster: &Monster, line: &str) -> String {
+        let key = format!("bosses.{}.{}", monster.id(), line);
+        if self.lexicon.has(&key) {
+            self.monster_line(&key, monster)
+        } else {
+            self.monster_line(&format!("bosses.{}", line), monster)
+        }
+    }
 
-            ctx.term
-                .write_line(&fmt_line!(
-                    ctx.hottext,
-                    if monster.is_difficult(player.level()) {
+    fn damage_line(&mut self, key: &str, monster: &Monster, damage: u64, weapon: &str) -> String {
+        let damage_str = self
+            .lexicon
+            .count("count.damage", damage)
+            .color(colors::DAMAGE)
+            .to_string();
+        let enemy = self.lexicon.monster(monster);
+        fmt_line!(
+            self.lexicon,
+            &self.lexicon.gendered_key(key, &enemy.gender),
+            damage = damage_str.as_str(),
+            weapon = weapon,
+            enemy = enemy.name.as_str(),
+            enemy_plural = enemy.plural.as_str(),
+            enemy_article = enemy.indefinite.as_str(),
+            enemy_proper = enemy.definite.as_str()
+        )
+    }
+}
+
+impl Renderer for ConsoleRenderer<'_> {
+    fn render(&mut self, event: GameEvent) {
+        let line = match event {
+            GameEvent::CaveScouted { name } => {
+                self.term.write_line("").unwrap();
+                fmt_line!(self.lexicon, "caves.scout", cave = name)
+            }
+            GameEvent::Clue { clue } => match clue {
+                Clue::Silence => get_line!(self.lexicon, "clues.silence"),
+                Clue::Sounds { difficulty } => {
+                    get_line!(self.lexicon, &format!("clues.sounds.{}", difficulty.id()))
+                }
+                Clue::Smell { monster } => self.monster_line("clues.smell", monster),
+                Clue::Tracks { monster, many } => self.monster_line(
+                    if *many {
+                        "clues.tracks.many"
+                    } else {
+                        "clues.tracks.one"
+                    },
+                    monster,
+                ),
+                Clue::Glimpse { monster } => self.monster_line("clues.glimpse", monster),
+            },
+            GameEvent::CaveEntered { name } => {
+                self.term.write_line("").unwrap();
+                fmt_line!(self.lexicon, "caves.enter", cave = name)
+            }
+            GameEvent::RoomEntered { kind, visited } => {
+                self.term.write_line("").unwrap();
+                if visited {
+                    let room = get_line!(self.lexicon, &format!("{}.name", kind.key()));
+                    fmt_line!(self.lexicon, "rooms.revisit", room = room.as_str())
+                } else if kind == RoomKind::Entrance {
+                    // Entering the cave already says as much
+                    return;
+                } else {
+                    get_line!(self.lexicon, kind.key())
+                }
+            }
+            GameEvent::NoEnemies => get_line!(self.lexicon, "combat.no-enemies"),
+            GameEvent::Encounter { monster, difficult } => {
+                self.term.write_line("").unwrap();
+                self.monster_line(
+                    if difficult {
                         "combat.encounter-hard"
                     } else {
                         "combat.encounter-easy"
                     },
-                    enemy = monster.name().as_str(),
-                    enemy_article = monster.article_name().as_str(),
-                    enemy_proper = monster.proper_name().as_str()
-                ))
-                .unwrap();
-
-            // Roll for initiative
-            let initiative =
-                ((player.level().max(1)) as f64 / (monster.level() * 2) as f64).clamp(0.0, 1.0);
-            if ctx.rng.gen_bool(initiative) {
-                ctx.term
-                    .write_line(
-                        fmt_line!(
-                            ctx.hottext,
-                            "combat.initiative",
-                            enemy = monster.name().as_str(),
-                            enemy_article = monster.article_name().as_str(),
-                            enemy_proper = monster.proper_name().as_str()
-                        )
-                        .as_str(),
-                    )
-                    .unwrap();
-            } else {
-                // Monster drastically outclasses player
-                if monster.is_difficult(player.level()) {
-                    // Roll to escape
-                    if ctx.rng.gen_bool(0.20) {
-                        ctx.term
-                            .write_line(&fmt_line!(
-                                ctx.hottext,
-                                "combat.retreat",
-                                enemy = monster.name().as_str(),
-                                enemy_article = monster.article_name().as_str(),
-                                enemy_proper = monster.proper_name().as_str()
-                            ))
-                            .unwrap();
-                        continue;
-                    }
+                    monster,
+                )
+            }
+            GameEvent::BossIntro { monster } => {
+                self.term.write_line("").unwrap();
+                self.boss_line(monster, "intro").bold().to_string()
+            }
+            GameEvent::BossPhase { monster, phase } => self.boss_line(monster, phase),
+            GameEvent::Initiative { .. } => return,
+            GameEvent::Retreat { monster } => self.monster_line("combat.retreat", monster),
+            GameEvent::Attack {
+                monster,
+                weapon,
+                damage,
+            } => {
+                let weapon = match weapon {
+                    Some(weapon) => self.lexicon.item(weapon).name,
+                    None => get_line!(self.lexicon, "combat.unarmed"),
+                };
+                self.damage_line("combat.attack", monster, damage, &weapon)
+            }
+            GameEvent::Attacked { monster, damage } => {
+                self.damage_line("combat.attacked", monster, damage, "")
+            }
+            GameEvent::PotionUsed { potion } => {
+                fmt_line!(
+                    self.lexicon,
+                    "potion.use",
+                    potion = self.lexicon.item(potion).name.as_str()
+                )
+            }
+            GameEvent::ItemUsed {
+                item,
+                cured,
+                granted,
+            } => item_use_lines(self.lexicon, item, &cured, &granted),
+            GameEvent::ItemUseFailed { item: Some(item) } => fmt_line!(
+                self.lexicon,
+                "combat.use-failed",
+                item = self.lexicon.item(item).name.as_str()
+            ),
+            GameEvent::ItemUseFailed { item: None } => {
+                get_line!(self.lexicon, "combat.use-missing")
+            }
+            GameEvent::Defend => get_line!(self.lexicon, "combat.defend"),
+            GameEvent::FleeFailed { monster } => self.monster_line("combat.flee-failed", monster),
+            GameEvent::AbilityRevealed { monster, ability } => {
+                match self.ability_line(monster, ability, "revealed", 0, None) {
+                    Some(line) => line,
+                    None => return,
                 }
-
-                let damage = monster.damage(&mut ctx.rng);
-                let applied_damage = player.add_damage(damage, world);
-                let damage_str = format!("{} damage", applied_damage.commas())
+            }
+            GameEvent::RangedShot { monster, ability } => {
+                match self.ability_line(monster, ability, "shot", 0, None) {
+                    Some(line) => line,
+                    None => return,
+                }
+            }
+            GameEvent::Dodged { monster, ability } => {
+                match self.ability_line(monster, ability, "dodged", 0, None) {
+                    Some(line) => line,
+                    None => return,
+                }
+            }
+            GameEvent::Regenerated {
+                monster,
+                ability,
+                hp,
+            } => match self.ability_line(monster, ability, "regenerated", hp, None) {
+                Some(line) => line,
+                None => return,
+            },
+            GameEvent::Summoned {
+                monster,
+                ability,
+                summoned,
+            } => match self.ability_line(monster, ability, "summoned", 0, Some(summoned)) {
+                Some(line) => line,
+                None => return,
+            },
+            GameEvent::Fled { monster, ability } => {
+                match self.ability_line(monster, ability, "fled", 0, None) {
+                    Some(line) => line,
+                    None => return,
+                }
+            }
+            GameEvent::Evaded { monster, ability } => {
+                match self.ability_line(monster, ability, "evaded", 0, None) {
+                    Some(line) => line,
+                    None => return,
+                }
+            }
+            GameEvent::Recovered { ability, hp } => {
+                let key = format!("abilities.{}.recovered", ability);
+                fmt_line!(
+                    self.lexicon,
+                    if self.lexicon.has(&key) {
+                        &key
+                    } else {
+                        "abilities.recovered"
+                    },
+                    hp = self.lexicon.number(hp).as_str()
+                )
+            }
+            GameEvent::Afflicted { monster, effect } => {
+                self.monster_line(&format!("effects.{}.inflicted", effect.id()), monster)
+            }
+            GameEvent::EffectTicked { effect, damage } => {
+                let damage_str = self
+                    .lexicon
+                    .count("count.damage", damage)
                     .color(colors::DAMAGE)
                     .to_string();
-                ctx.term
-                    .write_line(&fmt_line!(
-                        ctx.hottext,
-                        "combat.attacked",
-                        damage = damage_str.as_str(),
-                        enemy = monster.name().as_str(),
-                        enemy_article = monster.article_name().as_str(),
-                        enemy_proper = monster.proper_name().as_str()
-                    ))
+                fmt_line!(
+                    self.lexicon,
+                    &format!("effects.{}.tick", effect.id()),
+                    damage = damage_str.as_str()
+                )
+            }
+            GameEvent::EffectEnded { effect } => {
+                get_line!(self.lexicon, &format!("effects.{}.ended", effect.id()))
+            }
+            GameEvent::Taunt { monster } => self.monster_line("combat.player-turn", monster),
+            GameEvent::Slain {
+                monster,
+                first_strike,
+            } => self.monster_line(
+                // Monsters slain before they can attack get their own lines
+                if first_strike {
+                    "combat.initiative"
+                } else {
+                    "combat.survived"
+                },
+                monster,
+            ),
+            GameEvent::BossDefeated { monster } => self.boss_line(monster, "defeated"),
+            GameEvent::Reward {
+                xp,
+                gold,
+                loot,
+                drops,
+            } => {
+                self.term.write_line("").unwrap();
+                self.term
+                    .write_line(&get_line!(self.lexicon, "combat.reward"))
                     .unwrap();
 
-                // Attempt to heal
-                if let Some(potions_used) = player.auto_heal(world) {
-                    for potion in potions_used {
-                        ctx.term
-                            .write_line(&fmt_line!(
-                                ctx.hottext,
-                                "potion.use",
-                                potion = world
-                                    .get_item(&potion)
-                                    .expect("Potion ID pulled directly from world.items",)
-                                    .name()
-                                    .as_str()
-                            ))
-                            .unwrap();
+                let xp_name = get_line!(self.lexicon, "lingo.experience");
+                let gold_name = get_line!(self.lexicon, "lingo.gold");
+                let drop_lines: Vec<String> = drops
+                    .iter()
+                    .map(|(monster, _)| self.monster_line("combat.reward-drops", monster))
+                    .collect();
+                let lexicon = &*self.lexicon;
+                let xp_length = lexicon.number(xp).len();
+                let term = self.term;
+                let show_item = |(name, count): (&str, u64)| {
+                    let count_str =
+                        format!("x{:<width$} -", lexicon.number(count), width = xp_length)
+                            .color(colors::LOW_PRIORITY)
+                            .to_string();
+                    term.write_line(&format!("{} {}", count_str, name)).unwrap();
+                };
+                show_item((xp_name.color(colors::XP).to_string().as_ref(), xp));
+                show_item((gold_name.color(colors::GOLD).to_string().as_ref(), gold));
+                let show_items = |items: Vec<(&Item, u32)>| {
+                    for (item, count) in items {
+                        let name = lexicon.item(item);
+                        show_item((name.counted(lexicon.plural(count.into())), count.into()));
                     }
+                };
+                show_items(loot);
+                for (line, (_, items)) in drop_lines.iter().zip(drops) {
+                    term.write_line(line).unwrap();
+                    show_items(items);
                 }
-                if player.dead() {
-                    return CaveResult::Died;
-                } else {
-                    ctx.term
-                        .write_line(
-                            fmt_line!(
-                                ctx.hottext,
-                                "combat.player-turn",
-                                enemy = monster.name().as_str(),
-                                enemy_article = monster.article_name().as_str(),
-                                enemy_proper = monster.proper_name().as_str()
-                            )
-                            .as_str(),
-                        )
-                        .unwrap();
-
-                    ctx.term
-                        .write_line(
-                            fmt_line!(
-                                ctx.hottext,
-                                "combat.survived",
-                                enemy = monster.name().as_str(),
-                                enemy_article = monster.article_name().as_str(),
-                                enemy_proper = monster.proper_name().as_str()
-                            )
-                            .as_str(),
-                        )
-                        .unwrap();
-                    xp += monster.level() as u64 * 20
+                return;
+            }
+            GameEvent::Equipped { item } => fmt_line!(
+                self.lexicon,
+                "inventory.equipped",
+                item = self.lexicon.item(item).name.as_str()
+            ),
+            GameEvent::BetterGear { item, equipped } => fmt_line!(
+                self.lexicon,
+                "inventory.better",
+                item = self.lexicon.item(item).name.as_str(),
+                equipped = self.lexicon.item(equipped).name.as_str()
+            ),
+            GameEvent::LevelUp { gained, level, hp } => {
+                self.term.write_line("").unwrap();
+                fmt_line!(
+                    self.lexicon,
+                    if gained > 1 {
+                        "level.up-multiple"
+                    } else {
+                        "level.up"
+                    },
+                    gained = self.lexicon.number(gained).as_str(),
+                    level = self
+                        .lexicon
+                        .number(level)
+                        .color(colors::XP)
+                        .to_string()
+                        .as_str(),
+                    hp = self.lexicon.number(hp).as_str()
+                )
+            }
+            GameEvent::Died => {
+                self.term.write_line("").unwrap();
+                get_line!(self.lexicon, "combat.died")
+            }
+        };
+        self.term.write_line(&line).unwrap();
+    }
+
+    fn choose_action(
+        &mut self,
+        world: &World,
+        player: &Player,
+        monster: &Monster,
+    ) -> Option<Action> {
+        if *self.auto_battle {
+            return None;
+        }
+
+        let items = |potions: bool| -> Vec<(&Item, u32)> {
+            player
+                .inventory()
+                .iter()
+                .map(|(item, count)| {
+                    (
+                        world
+                            .get_item(item)
+                            .expect("world.items should not have mutated"),
+                        *count,
+                    )
+                })
+                .filter(|(item, _)| match item.kind() {
+                    ItemKind::Potion { .. } => potions,
+                    _ => !potions && item.usable(),
+                })
+                .collect()
+        };
+        let (potions, usables) = (items(true), items(false));
+
+        let mut choices = vec![CombatChoice::Attack, CombatChoice::Defend];
+        if !potions.is_empty() {
+            choices.push(CombatChoice::Drink);
+        }
+        if !usables.is_empty() {
+            choices.push(CombatChoice::Use);
+        }
+        choices.push(CombatChoice::Flee);
+        choices.push(CombatChoice::AutoBattle);
+
+        let chance = self
+            .lexicon
+            .number((engine::flee_chance(player, monster) * 100.0).round() as u64);
+        let labels: Vec<String> = choices
+            .iter()
+            .map(|choice| match choice {
+                CombatChoice::Flee => {
+                    fmt_line!(self.lexicon, "combat.action.flee", chance = chance.as_str())
+                }
+                _ => get_line!(
+                    self.lexicon,
+                    match choice {
+                        CombatChoice::Attack => "combat.action.attack",
+                        CombatChoice::Defend => "combat.action.defend",
+                        CombatChoice::Drink => "combat.action.drink",
+                        CombatChoice::Use => "combat.action.use",
+                        _ => "combat.action.auto-battle",
+                    }
+                ),
+            })
+            .collect();
+        let prompt = fmt_line!(
+            self.lexicon,
+            "combat.action.prompt",
+            hp = self.lexicon.number(player.hp_remaining()).as_str(),
+            max_hp = self.lexicon.number(player.hp()).as_str()
+        );
+
+        loop {
+            self.term.write_line("").unwrap();
+            let choice = choose(
+                self.term,
+                &prompt,
+                &labels.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+            );
+            let items = match choices[choice] {
+                CombatChoice::Attack => return Some(Action::Attack),
+                CombatChoice::Defend => return Some(Action::Defend),
+                CombatChoice::Flee => return Some(Action::Flee),
+                CombatChoice::AutoBattle => {
+                    *self.auto_battle = true;
+                    let line = get_line!(self.lexicon, "combat.auto-battle");
+                    self.term.write_line(&line).unwrap();
+                    return None;
                 }
+                CombatChoice::Drink => &potions,
+                CombatChoice::Use => &usables,
+            };
+
+            let mut item_choices: Vec<String> = items
+                .iter()
+                .map(|(item, count)| {
+                    fmt_line!(
+                        self.lexicon,
+                        "combat.action.item",
+                        item = self.lexicon.item(item).name.as_str(),
+                        count = self.lexicon.number(*count).as_str()
+                    )
+                })
+                .collect();
+            item_choices.push(get_line!(self.lexicon, "interface.back"));
+            let item_prompt = get_line!(self.lexicon, "inventory.prompt");
+            self.term.write_line("").unwrap();
+            let index = choose(
+                self.term,
+                &item_prompt,
+                &item_choices
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<&str>>(),
+            );
+            if let Some((item, _)) = items.get(index) {
+                return Some(Action::Use {
+                    item: item.id().to_string(),
+                });
             }
         }
     }
+}
 
-    let reward = CaveReward {
-        xp,
-        gold: cave.gold,
-        loot: cave.loot,
-    };
-    CaveResult::Survived { reward }
+pub fn enter_cave(world: &mut World, player: &mut Player, ctx: &mut Context) -> CaveResult {
+    // Cave
+    let mut caves = engine::generate_caves(world, player, &mut ctx.rng);
+    let cave_names: Vec<String> = caves.iter().map(|cave| ctx.lexicon.cave(cave)).collect();
+    for (cave, name) in caves.iter().zip(&cave_names) {
+        let (rng, mut renderer) = ConsoleRenderer::split(ctx);
+        let clues = engine::scout_cave(world, player, cave, rng);
+        renderer.render(GameEvent::CaveScouted { name });
+        for clue in &clues {
+            renderer.render(GameEvent::Clue { clue });
+        }
+    }
+    spacer(ctx);
+    let prompt = get_line!(ctx.lexicon, "caves.approach");
+    let choice = get_choice(
+        ctx,
+        &prompt,
+        &cave_names.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+    );
+    let mut cave = caves.remove(choice);
+
+    let (_, mut renderer) = ConsoleRenderer::split(ctx);
+    let mut expedition = engine::enter_cave(world, &cave_names[choice], &mut renderer);
+    let mut room = Cave::ENTRANCE;
+    loop {
+        let (rng, mut renderer) = ConsoleRenderer::split(ctx);
+        let result = engine::explore_room(
+            world,
+            player,
+            &mut cave,
+            &mut expedition,
+            room,
+            rng,
+            &mut renderer,
+        );
+        if let RoomResult::Died = result {
+            break CaveResult::Died;
+        }
+
+        match choose_passage(&cave, room, ctx) {
+            Some(next) => room = next,
+            None => {
+                break CaveResult::Survived {
+                    reward: expedition.reward,
+                }
+            }
+        }
+    }
+}
+
+/// Asks the player where to go from `room`. Returns `None` once they leave the
+/// cave.
+fn choose_passage(cave: &Cave, room: usize, ctx: &mut Context) -> Option<usize> {
+    spacer(ctx);
+
+    let mut destinations: Vec<Option<usize>> = Vec::new();
+    let mut choices: Vec<String> = Vec::new();
+    // Unexplored passages first, so the default choice goes deeper
+    let mut passages = cave.rooms[room].passages.clone();
+    passages.sort_by_key(|passage| cave.rooms[*passage].visited);
+    for passage in passages {
+        let next = &cave.rooms[passage];
+        choices.push(if next.visited {
+            let name = get_line!(ctx.lexicon, &format!("{}.name", next.kind.key()));
+            fmt_line!(ctx.lexicon, "rooms.passage-back", room = name.as_str())
+        } else {
+            get_line!(ctx.lexicon, &format!("{}.passage", next.kind.key()))
+        });
+        destinations.push(Some(passage));
+    }
+    if room == Cave::ENTRANCE {
+        choices.push(get_line!(ctx.lexicon, "rooms.leave"));
+        destinations.push(None);
+    } else if !cave.rooms[room].passages.contains(&Cave::ENTRANCE) {
+        choices.push(get_line!(ctx.lexicon, "rooms.retreat"));
+        destinations.push(Some(Cave::ENTRANCE));
+    }
+
+    let prompt = get_line!(ctx.lexicon, "rooms.prompt");
+    let choice = get_choice(
+        ctx,
+        &prompt,
+        &choices.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+    );
+    destinations[choice]
 }
 
 pub fn show_cave_reward(
@@ -230,73 +664,296 @@ pub fn show_cave_reward(
     reward: CaveReward,
     ctx: &mut Context,
 ) -> CaveSurvivedChoice {
-    spacer(ctx);
+    let (rng, mut renderer) = ConsoleRenderer::split(ctx);
+    engine::claim_reward(world, player, reward, &mut renderer);
+    let mut merchant = Merchant::new(world, rng);
 
-    // Loot
-    player.add_xp(reward.xp);
-    player.add_gold(reward.gold);
+    let prompt = get_line!(ctx.lexicon, "interface.generic-menu");
+    loop {
+        spacer(ctx);
+
+        let choices: [&str; 5] = [
+            &get_line!(ctx.lexicon, "interface.next-cave"),
+            &get_line!(ctx.lexicon, "interface.manage-inventory"),
+            &get_line!(ctx.lexicon, "interface.visit-merchant"),
+            &get_line!(
+                ctx.lexicon,
+                if ctx.auto_battle {
+                    "interface.auto-battle-off"
+                } else {
+                    "interface.auto-battle-on"
+                }
+            ),
+            &get_line!(ctx.lexicon, "interface.show-status"),
+        ];
+        let choice_index = get_choice(ctx, &prompt, &choices);
+        match choice_index {
+            0 => break CaveSurvivedChoice::Continue,
+            1 => manage_inventory(world, player, ctx),
+            2 => show_merchant(world, player, &mut merchant, ctx),
+            3 => ctx.auto_battle = !ctx.auto_battle,
+            _ => break CaveSurvivedChoice::ShowStatusReport,
+        }
+    }
+}
+
+fn gold_str(amount: u64, ctx: &mut Context) -> String {
+    ctx.lexicon
+        .count("count.gold", amount)
+        .color(colors::GOLD)
+        .to_string()
+}
+
+pub fn show_merchant(
+    world: &World,
+    player: &mut Player,
+    merchant: &mut Merchant,
+    ctx: &mut Context,
+) {
+    spacer(ctx);
 
     ctx.term
-        .write_line(&get_line!(ctx.hottext, "combat.reward"))
+        .write_line(&get_line!(ctx.lexicon, "merchant.greeting"))
         .unwrap();
 
-    let xp_length = reward.xp.commas().len();
-    let show_item = |(name, count): (&str, u32)| {
-        let count_str = format!("x{:<width$} -", count.commas(), width = xp_length)
-            .color(colors::LOW_PRIORITY)
-            .to_string();
-        println!("{} {}", count_str, name);
-    };
-    show_item((
-        "xp".color(colors::XP).to_string().as_ref(),
-        reward.xp as u32,
-    ));
-    show_item((
-        "gold".color(colors::GOLD).to_string().as_ref(),
-        reward.gold as u32,
-    ));
-    for (item, count) in reward.loot {
-        for _ in 0..count {
-            player.add_item(&item);
+    let prompt = get_line!(ctx.lexicon, "interface.generic-menu");
+    let choices: [&str; 3] = [
+        &get_line!(ctx.lexicon, "merchant.buy"),
+        &get_line!(ctx.lexicon, "merchant.sell"),
+        &get_line!(ctx.lexicon, "merchant.leave"),
+    ];
+    loop {
+        spacer(ctx);
+
+        let gold = gold_str(player.gold(), ctx);
+        ctx.term
+            .write_line(&fmt_line!(
+                ctx.lexicon,
+                "merchant.purse",
+                gold = gold.as_str()
+            ))
+            .unwrap();
+        let choice_index = get_choice(ctx, &prompt, &choices);
+        match choice_index {
+            0 => trade(world, player, merchant, true, ctx),
+            1 => trade(world, player, merchant, false, ctx),
+            _ => break,
         }
-        show_item((
-            world
-                .get_item(&item)
-                .expect("world.items should not have mutated")
-                .name()
-                .as_ref(),
-            count,
-        ));
     }
-    // TODO: "You leveled up!"
+}
+
+/// Lets the player buy from or sell to the merchant until they back out.
+fn trade(
+    world: &World,
+    player: &mut Player,
+    merchant: &mut Merchant,
+    buying: bool,
+    ctx: &mut Context,
+) {
+    loop {
+        spacer(ctx);
+
+        let mut wares: Vec<(&Item, u32)> = if buying {
+            merchant.inventory()
+        } else {
+            player.inventory()
+        }
+        .iter()
+        .map(|(item, count)| {
+            (
+                world
+                    .get_item(item)
+                    .expect("world.items should not have mutated"),
+                *count,
+            )
+        })
+        .collect();
+        wares.sort_by_key(|(item, _)| item.value());
+
+        if wares.is_empty() {
+            ctx.term
+                .write_line(&get_line!(
+                    ctx.lexicon,
+                    if buying {
+                        "merchant.sold-out"
+                    } else {
+                        "merchant.nothing-to-sell"
+                    }
+                ))
+                .unwrap();
+            break;
+        }
+
+        let mut choices: Vec<String> = wares
+            .iter()
+            .map(|(item, count)| {
+                let price = if buying {
+                    merchant.price(item)
+                } else {
+                    merchant.offer(item)
+                };
+                let price = gold_str(price, ctx);
+                fmt_line!(
+                    ctx.lexicon,
+                    "merchant.ware",
+                    item = ctx.lexicon.item(item).name.as_str(),
+                    price = price.as_str(),
+                    count = ctx.lexicon.number(count).as_str()
+                )
+            })
+            .collect();
+        choices.push(get_line!(ctx.lexicon, "interface.back"));
+        let prompt = get_line!(
+            ctx.lexicon,
+            if buying {
+                "merchant.buy-prompt"
+            } else {
+                "merchant.sell-prompt"
+            }
+        );
+        let choice_index = get_choice(
+            ctx,
+            &prompt,
+            &choices.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+        );
+        let item = match wares.get(choice_index) {
+            Some((item, _)) => *item,
+            None => break,
+        };
+
+        let result = if buying {
+            merchant.buy(player, item)
+        } else {
+            merchant.sell(player, item)
+        };
+        let line = match result {
+            Ok(price) => {
+                let price = gold_str(price, ctx);
+                fmt_line!(
+                    ctx.lexicon,
+                    if buying {
+                        "merchant.bought"
+                    } else {
+                        "merchant.sold"
+                    },
+                    item = ctx.lexicon.item(item).name.as_str(),
+                    price = price.as_str()
+                )
+            }
+            Err(error) => fmt_line!(
+                ctx.lexicon,
+                error.key(),
+                item = ctx.lexicon.item(item).name.as_str()
+            ),
+        };
+        ctx.term.write_line(&line).unwrap();
+    }
+}
+
+/// Has the player pick the class they start a run as. Without any classes
+/// loaded, everyone is a plain adventurer.
+pub fn choose_class(world: &World, ctx: &mut Context) -> Class {
+    let classes: Vec<&Class> = world.classes().values().collect();
+    if classes.is_empty() {
+        return Class::default();
+    }
 
     spacer(ctx);
 
-    let prompt = get_line!(ctx.hottext, "interface.generic-menu");
-    let choices: [&str; 2] = [
-        &get_line!(ctx.hottext, "interface.next-cave"),
-        &get_line!(ctx.hottext, "interface.show-status"),
-    ];
-    let choice_index = get_choice(ctx, &prompt, &choices);
-    match choice_index {
-        0 => CaveSurvivedChoice::Continue,
-        _ => CaveSurvivedChoice::ShowStatusReport,
+    let prompt = get_line!(ctx.lexicon, "classes.prompt");
+    let choices: Vec<String> = classes
+        .iter()
+        .map(|class| {
+            let name = ctx.lexicon.class(class).bold().to_string();
+            let description = ctx.lexicon.class_description(class);
+            if description.is_empty() {
+                name
+            } else {
+                fmt_line!(
+                    ctx.lexicon,
+                    "classes.entry",
+                    class = name.as_str(),
+                    description = description.as_str()
+                )
+            }
+        })
+        .collect();
+    let choices: Vec<&str> = choices.iter().map(|s| s.as_str()).collect();
+    classes[get_choice(ctx, &prompt, &choices)].clone()
+}
+
+/// The name of the class with `id`, even if it's no longer loaded.
+fn class_name(world: &World, id: &str, lexicon: &Lexicon) -> String {
+    match world.get_class(id) {
+        Some(class) => lexicon.class(class),
+        None => lexicon.class(&Class {
+            id: id.to_string(),
+            name: id.to_string(),
+            ..Class::default()
+        }),
+    }
+}
+
+pub fn autosave(file: &Path, world: &World, player: &Player, ctx: &mut Context) {
+    if let Err(error) = SaveFile::new(world, player, ctx).store(file) {
+        ctx.term
+            .write_line(&fmt_line!(
+                ctx.lexicon,
+                "save.failed",
+                error = error.to_string().as_str()
+            ))
+            .unwrap();
     }
 }
 
 pub fn show_status(world: &World, player: &Player, ctx: &mut Context) {
     spacer(ctx);
 
-    println!(
-        "Level {} ({} xp), {}/{} hp, {} gold, {} items, {} armor",
-        player.level().commas(),
-        player.xp().commas(),
-        player.hp_remaining(),
-        player.hp(),
-        player.gold(),
-        player.item_count().commas(),
-        player.defense(world).commas(),
-    );
+    ctx.term
+        .write_line(&fmt_line!(
+            ctx.lexicon,
+            "interface.status",
+            class = ctx.lexicon.class(player.class()).as_str(),
+            level = ctx.lexicon.number(player.level()).as_str(),
+            xp = ctx.lexicon.number(player.xp()).as_str(),
+            hp = ctx.lexicon.number(player.hp_remaining()).as_str(),
+            max_hp = ctx.lexicon.number(player.hp()).as_str(),
+            gold = ctx.lexicon.count("count.gold", player.gold()).as_str(),
+            items = ctx
+                .lexicon
+                .count("count.item", player.item_count().into())
+                .as_str(),
+            armor = ctx.lexicon.number(player.defense(world)).as_str()
+        ))
+        .unwrap();
+    if !player.effects().is_empty() {
+        let effects: Vec<String> = player
+            .effects()
+            .iter()
+            .map(|(effect, remaining)| {
+                let unit = match effect.tick() {
+                    Tick::Round => "count.round",
+                    Tick::Cave => "count.cave",
+                };
+                fmt_line!(
+                    ctx.lexicon,
+                    "effects.entry",
+                    effect = get_line!(ctx.lexicon, &format!("effects.{}.name", effect.id()))
+                        .color(colors::DAMAGE)
+                        .to_string()
+                        .as_str(),
+                    remaining = ctx.lexicon.count(unit, (*remaining).into()).as_str()
+                )
+            })
+            .collect();
+        ctx.term
+            .write_line(&fmt_line!(
+                ctx.lexicon,
+                "interface.effects",
+                effects = effects.join(", ").as_str()
+            ))
+            .unwrap();
+    }
     wait_any_key(ctx);
 }
 
@@ -310,63 +967,371 @@ pub fn show_inventory(world: &World, player: &Player, ctx: &mut Context) {
             (
                 world
                     .get_item(item)
-                    .expect("world.items should not have mutated")
-                    .name(),
+                    .expect("world.items should not have mutated"),
                 *count,
             )
         })
+        .map(|(item, count)| {
+            let name = ctx.lexicon.item(item);
+            (
+                name.counted(ctx.lexicon.plural(count.into())).to_string(),
+                count,
+            )
+        })
         .collect::<Vec<(String, u32)>>();
     inventory.sort_by_key(|e| e.1);
     inventory.reverse();
 
+    let gold_name = get_line!(ctx.lexicon, "lingo.gold");
+    let term = &ctx.term;
     let show_item = |(name, count): (&str, u32)| {
         let count_str = format!(
             "x{:<width$} -",
-            count.commas(),
-            width = player.gold().commas().len()
+            ctx.lexicon.number(count),
+            width = ctx.lexicon.number(player.gold()).len()
         )
         .color(colors::LOW_PRIORITY)
         .to_string();
-        println!("{} {}", count_str, name);
+        term.write_line(&format!("{} {}", count_str, name)).unwrap();
     };
     show_item((
-        "gold".color(colors::GOLD).to_string().as_ref(),
+        gold_name.color(colors::GOLD).to_string().as_ref(),
         player.gold() as u32,
     ));
     inventory
         .into_iter()
         .for_each(|(name, count)| show_item((&name, count)));
+
+    if !player.equipment().is_empty() {
+        spacer(ctx);
+        for slot in player.equipment().keys() {
+            let item = player
+                .equipped(world, *slot)
+                .expect("Slot pulled from player.equipment");
+            let line = fmt_line!(
+                ctx.lexicon,
+                "inventory.slot",
+                slot = get_line!(ctx.lexicon, slot.key()).as_str(),
+                item = ctx.lexicon.item(item).name.as_str()
+            );
+            ctx.term.write_line(&line).unwrap();
+        }
+    }
 }
 
-pub fn show_death_screen(world: &World, player: &Player, ctx: &mut Context) -> GameOverChoice {
+enum InventoryAction {
+    Equip,
+    Unequip,
+    Drink,
+    Use,
+    Drop,
+    Back,
+}
+
+/// Lets the player equip, unequip, drop and drink items until they back out.
+pub fn manage_inventory(world: &World, player: &mut Player, ctx: &mut Context) {
+    loop {
+        show_inventory(world, player, ctx);
+        spacer(ctx);
+
+        // Equipped items are listed first, then the rest of the bag
+        let mut entries: Vec<(&Item, Option<Slot>)> = player
+            .equipment()
+            .keys()
+            .filter_map(|slot| Some((player.equipped(world, *slot)?, Some(*slot))))
+            .collect();
+        entries.extend(
+            player
+                .inventory()
+                .keys()
+                .filter_map(|item| world.get_item(item))
+                .map(|item| (item, None)),
+        );
+
+        let mut choices: Vec<String> = entries
+            .iter()
+            .map(|(item, slot)| match slot {
+                Some(slot) => fmt_line!(
+                    ctx.lexicon,
+                    "inventory.slot",
+                    slot = get_line!(ctx.lexicon, slot.key()).as_str(),
+                    item = ctx.lexicon.item(item).name.as_str()
+                ),
+                None => ctx.lexicon.item(item).name,
+            })
+            .collect();
+        choices.push(get_line!(ctx.lexicon, "inventory.equip-best"));
+        choices.push(get_line!(ctx.lexicon, "interface.back"));
+        let prompt = get_line!(ctx.lexicon, "inventory.prompt");
+        let choice_index = get_choice(
+            ctx,
+            &prompt,
+            &choices.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+        );
+
+        let (item, slot) = match entries.get(choice_index) {
+            Some(entry) => *entry,
+            None if choice_index == entries.len() => {
+                player.auto_equip(world);
+                continue;
+            }
+            None => break,
+        };
+
+        let actions = match (slot, item.kind()) {
+            (Some(_), _) => vec![InventoryAction::Unequip],
+            (None, ItemKind::Weapon { .. }) | (None, ItemKind::Armor { .. }) => {
+                vec![InventoryAction::Equip, InventoryAction::Drop]
+            }
+            (None, ItemKind::Potion { .. }) => vec![InventoryAction::Drink, InventoryAction::Drop],
+            (None, ItemKind::Collectible {}) if item.usable() => {
+                vec![InventoryAction::Use, InventoryAction::Drop]
+            }
+            (None, ItemKind::Collectible {}) => vec![InventoryAction::Drop],
+        };
+        let action_choices: Vec<String> = actions
+            .iter()
+            .chain(std::iter::once(&InventoryAction::Back))
+            .map(|action| {
+                get_line!(
+                    ctx.lexicon,
+                    match action {
+                        InventoryAction::Equip => "inventory.equip",
+                        InventoryAction::Unequip => "inventory.unequip",
+                        InventoryAction::Drink => "inventory.drink",
+                        InventoryAction::Use => "inventory.use",
+                        InventoryAction::Drop => "inventory.drop",
+                        InventoryAction::Back => "interface.back",
+                    }
+                )
+            })
+            .collect();
+
+        spacer(ctx);
+
+        let prompt = fmt_line!(
+            ctx.lexicon,
+            "inventory.action-prompt",
+            item = ctx.lexicon.item(item).name.as_str()
+        );
+        let action_index = get_choice(
+            ctx,
+            &prompt,
+            &action_choices
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<&str>>(),
+        );
+        let line = match actions.get(action_index) {
+            Some(InventoryAction::Equip) => {
+                player.equip(item).expect("Item pulled from the inventory.");
+                fmt_line!(
+                    ctx.lexicon,
+                    "inventory.equipped",
+                    item = ctx.lexicon.item(item).name.as_str()
+                )
+            }
+            Some(InventoryAction::Unequip) => {
+                player
+                    .unequip(slot.expect("Only equipped items can be unequipped."))
+                    .expect("Slot pulled from player.equipment");
+                fmt_line!(
+                    ctx.lexicon,
+                    "inventory.unequipped",
+                    item = ctx.lexicon.item(item).name.as_str()
+                )
+            }
+            Some(InventoryAction::Drink) | Some(InventoryAction::Use) => {
+                let used = player
+                    .use_item(item)
+                    .expect("Item pulled from the inventory.");
+                item_use_lines(&mut ctx.lexicon, item, &used.cured, &used.granted)
+            }
+            Some(InventoryAction::Drop) => {
+                player
+                    .remove_item(item.id())
+                    .expect("Item pulled from the inventory.");
+                fmt_line!(
+                    ctx.lexicon,
+                    "inventory.dropped",
+                    item = ctx.lexicon.item(item).name.as_str()
+                )
+            }
+            Some(InventoryAction::Back) | None => continue,
+        };
+        ctx.term.write_line(&line).unwrap();
+    }
+}
+
+/// Adds the finished run to the leaderboard file, returning the leaderboard and
+/// the index of the new run.
+pub fn record_run(
+    file: &Path,
+    world: &World,
+    player: &Player,
+    locale: &str,
+    ctx: &mut Context,
+) -> (Leaderboard, usize) {
+    // A leaderboard that couldn't be read at all is left alone, so it isn't
+    // overwritten with just this run
+    let (mut leaderboard, store) = match Leaderboard::load(file) {
+        Ok(leaderboard) => (leaderboard, true),
+        Err(error) => {
+            let corrupt = leaderboard::is_corrupt(error.as_ref());
+            ctx.term
+                .write_line(&fmt_line!(
+                    ctx.lexicon,
+                    if corrupt {
+                        "leaderboard.corrupt"
+                    } else {
+                        "leaderboard.unreadable"
+                    },
+                    error = error.to_string().as_str()
+                ))
+                .unwrap();
+            if corrupt {
+                leaderboard::quarantine(file);
+            }
+            (Leaderboard::default(), corrupt)
+        }
+    };
+    let current = leaderboard.record(Run::new(world, player, locale, ctx.seed));
+    if !store {
+        return (leaderboard, current);
+    }
+    if let Err(error) = leaderboard.store(file) {
+        ctx.term
+            .write_line(&fmt_line!(
+                ctx.lexicon,
+                "leaderboard.store-failed",
+                error = error.to_string().as_str()
+            ))
+            .unwrap();
+    }
+    (leaderboard, current)
+}
+
+pub fn show_leaderboards(
+    world: &World,
+    leaderboard: &Leaderboard,
+    current: usize,
+    ctx: &mut Context,
+) {
+    const SHOWN_RUNS: usize = 10;
+
+    let prompt = get_line!(ctx.lexicon, "leaderboard.prompt");
+    let mut choices: Vec<String> = Metric::ALL
+        .iter()
+        .map(|metric| get_line!(ctx.lexicon, metric.key()))
+        .collect();
+    choices.push(get_line!(ctx.lexicon, "interface.back"));
+    let choices: Vec<&str> = choices.iter().map(|s| s.as_str()).collect();
+
+    loop {
+        spacer(ctx);
+
+        let choice_index = get_choice(ctx, &prompt, &choices);
+        let metric = match Metric::ALL.get(choice_index) {
+            Some(metric) => *metric,
+            None => break,
+        };
+
+        spacer(ctx);
+
+        for (rank, index, run) in leaderboard.top(metric, SHOWN_RUNS, current) {
+            let line = fmt_line!(
+                ctx.lexicon,
+                "leaderboard.entry",
+                rank = ctx.lexicon.number(rank + 1).as_str(),
+                class = class_name(world, &run.class, &ctx.lexicon).as_str(),
+                level = ctx.lexicon.number(run.level).as_str(),
+                xp = ctx.lexicon.number(run.xp).as_str(),
+                net_worth = ctx.lexicon.count("count.gold", run.net_worth).as_str(),
+                caves = ctx.lexicon.count("count.cave", run.caves).as_str(),
+                monsters = ctx.lexicon.count("count.monster", run.monsters).as_str()
+            );
+            if index == current {
+                ctx.term
+                    .write_line(&line.color(colors::HIGHLIGHT).bold().to_string())
+                    .unwrap();
+            } else {
+                ctx.term.write_line(&line).unwrap();
+            }
+        }
+    }
+}
+
+pub fn show_death_screen(
+    world: &World,
+    player: &Player,
+    leaderboard: &Leaderboard,
+    current_run: usize,
+    ctx: &mut Context,
+) -> GameOverChoice {
     spacer(ctx);
 
     ctx.term
-        .write_line(&get_line!(ctx.hottext, "combat.died"))
+        .write_line(&get_line!(ctx.lexicon, "combat.game-over"))
         .unwrap();
     ctx.term
-        .write_line(&get_line!(ctx.hottext, "combat.game-over"))
+        .write_line(&fmt_line!(
+            ctx.lexicon,
+            "combat.game-over-stats",
+            caves = ctx.lexicon.count("count.cave", world.stats.caves).as_str(),
+            monsters = ctx
+                .lexicon
+                .count("count.monster", world.stats.monsters)
+                .as_str(),
+            gold = ctx.lexicon.count("count.gold", player.gold()).as_str(),
+            items = ctx
+                .lexicon
+                .count("count.item", player.item_count().into())
+                .as_str()
+        ))
+        .unwrap();
+    ctx.term
+        .write_line(&fmt_line!(
+            ctx.lexicon,
+            "combat.game-over-level",
+            level = ctx.lexicon.number(player.level()).as_str(),
+            xp = ctx.lexicon.number(player.xp()).as_str(),
+            net_worth = ctx
+                .lexicon
+                .count("count.gold", player.net_worth(world))
+                .as_str()
+        ))
+        .unwrap();
+    if !world.stats.bosses.is_empty() {
+        ctx.term
+            .write_line(&fmt_line!(
+                ctx.lexicon,
+                "combat.game-over-bosses",
+                bosses = ctx
+                    .lexicon
+                    .count("count.boss", world.stats.bosses.len() as u64)
+                    .as_str()
+            ))
+            .unwrap();
+    }
+    ctx.term
+        .write_line(&fmt_line!(
+            ctx.lexicon,
+            "interface.seed",
+            seed = ctx
+                .seed
+                .to_string()
+                .color(colors::LOW_PRIORITY)
+                .to_string()
+                .as_str()
+        ))
         .unwrap();
-    println!(
-        "{} caves, {} monsters, {} gold, and {} items.",
-        (world.stats.caves / 2).commas(),
-        world.stats.monsters.commas(),
-        player.gold().commas(),
-        player.item_count().commas(),
-    );
-    println!(
-        "That leaves you with a final level of {} ({} xp) and a net worth of {} gold.",
-        player.level().commas(),
-        player.xp().commas(),
-        player.net_worth(world).commas(),
-    );
 
-    let prompt = get_line!(ctx.hottext, "interface.generic-menu");
+    let prompt = get_line!(ctx.lexicon, "interface.generic-menu");
     let choices: [&str; 4] = [
-        &get_line!(ctx.hottext, "interface.retry"),
-        &get_line!(ctx.hottext, "interface.view-inventory"),
-        &get_line!(ctx.hottext, "interface.leaderboards"),
-        &get_line!(ctx.hottext, "interface.quit"),
+        &get_line!(ctx.lexicon, "interface.retry"),
+        &get_line!(ctx.lexicon, "interface.view-inventory"),
+        &get_line!(ctx.lexicon, "interface.leaderboards"),
+        &get_line!(ctx.lexicon, "interface.quit"),
     ];
     loop {
         spacer(ctx);
@@ -375,7 +1340,7 @@ pub fn show_death_screen(world: &World, player: &Player, ctx: &mut Context) -> G
         match choice_index {
             0 => break GameOverChoice::Retry,
             1 => show_inventory(world, player, ctx),
-            2 => todo!(),
+            2 => show_leaderboards(world, leaderboard, current_run, ctx),
             _ => break GameOverChoice::Quit,
         }
     }
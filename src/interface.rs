@@ -1,64 +1,48 @@
-use std::fmt::Display;
 use std::io::Write;
 
 use colored::*;
 use console::{Key, Term};
-use hottext::HotText;
+use rand::SeedableRng;
 
 use crate::colors;
+use crate::locale::{get_line, Lexicon};
 
-pub trait Comma
-where
-    Self: Display,
-{
-    /// Formats number with commas separating every three decimal places
-    /// # Examples
-    /// ```
-    /// let s = 6000.commas();
-    /// assert_eq(s, "6,000");
-    /// ```
-    fn commas(&self) -> String {
-        let raw_display = format!("{}", self);
-        raw_display
-            .chars()
-            .rev()
-            .fold((String::new(), 0), |(mut output, mut length), char| {
-                length += 1;
-                if length % 3 == 1 && length >= 3 {
-                    output.push(',');
-                }
-                output.push(char);
-
-                (output, length)
-            })
-            .0
-            .chars()
-            .rev()
-            .collect()
-    }
-}
-
-impl Comma for u8 {}
-impl Comma for u16 {}
-impl Comma for u32 {}
-impl Comma for u64 {}
-impl Comma for i8 {}
-impl Comma for i16 {}
-impl Comma for i32 {}
-impl Comma for i64 {}
+/// RNG driving every game decision. Seedable so runs can be replayed, and
+/// serializable so it can be saved mid-run.
+pub type GameRng = rand_chacha::ChaCha8Rng;
 
 pub struct Context {
-    pub hottext: HotText<rand::rngs::ThreadRng>,
+    /// Lines shown to the player, and the names and grammar they're built
+    /// with
+    pub lexicon: Lexicon,
     pub term: Term,
-    pub rng: rand::rngs::ThreadRng,
+    pub rng: GameRng,
+    /// Seed the current run was started with
+    pub seed: u64,
+    /// Whether fights play out without asking the player what to do
+    pub auto_battle: bool,
+}
+
+impl Context {
+    /// Restarts the game and flavor text RNGs from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = GameRng::seed_from_u64(seed);
+        self.lexicon.reseed(seed);
+    }
 }
 
 impl Default for Context {
     fn default() -> Self {
+        let seed = rand::random();
+        let mut lexicon = Lexicon::default();
+        lexicon.reseed(seed);
         Self {
-            hottext: HotText::default(),
+            lexicon,
             term: Term::stdout(),
-            rng: rand::thread_rng(),
+            rng: GameRng::seed_from_u64(seed),
+            seed,
+            auto_battle: false,
         }
     }
 }
@@ -68,40 +52,46 @@ pub fn read_choice<'a>(ctx: &mut Context, prompt: &str, choices: &[&'a str]) ->
     ctx.term
         .write_line(&format!("{} ({})", prompt, choices.join(", ")))
         .unwrap();
+    let type_prompt = get_line!(ctx.lexicon, "interface.type-prompt");
+    let invalid_choice = get_line!(ctx.lexicon, "interface.invalid-choice");
     loop {
-        ctx.term.write_all(b"? ").unwrap();
+        ctx.term.write_all(type_prompt.as_bytes()).unwrap();
         if let Ok(mut input) = ctx.term.read_line() {
             input = input.trim().to_lowercase();
             if let Some(index) = choices.iter().position(|&option| option == input) {
                 break index;
             }
         }
-        println!("That's not a valid choice.");
+        ctx.term.write_line(&invalid_choice).unwrap();
     }
 }
 
 /// Prompts the user with a menu to select one of N choices.
 pub fn get_choice<'a>(ctx: &mut Context, prompt: &str, choices: &[&'a str]) -> usize {
-    ctx.term.write_line(prompt).unwrap();
+    choose(&ctx.term, prompt, choices)
+}
+
+/// Like [`get_choice`], for when the rest of the [`Context`] is borrowed.
+pub fn choose(term: &Term, prompt: &str, choices: &[&str]) -> usize {
+    term.write_line(prompt).unwrap();
 
     let mut selection = 0;
     let last_index = choices.len() - 1;
-    ctx.term.hide_cursor().unwrap();
+    term.hide_cursor().unwrap();
     loop {
         for (index, option) in choices.iter().enumerate() {
             let prefix = if index == selection { '>' } else { ' ' };
-            ctx.term
-                .write_line(
-                    &format!("{} {}", prefix, option)
-                        .color(colors::INPUT)
-                        .to_string(),
-                )
-                .unwrap();
+            term.write_line(
+                &format!("{} {}", prefix, option)
+                    .color(colors::INPUT)
+                    .to_string(),
+            )
+            .unwrap();
         }
 
-        match ctx.term.read_key().unwrap() {
+        match term.read_key().unwrap() {
             Key::Enter | Key::Char('e') => {
-                ctx.term.show_cursor().unwrap();
+                term.show_cursor().unwrap();
                 break selection;
             }
             Key::ArrowUp | Key::Char('w') => selection = selection.saturating_sub(1),
@@ -110,7 +100,7 @@ pub fn get_choice<'a>(ctx: &mut Context, prompt: &str, choices: &[&'a str]) -> u
             Key::End => selection = last_index,
             _ => {}
         }
-        ctx.term.clear_last_lines(choices.len()).unwrap();
+        term.clear_last_lines(choices.len()).unwrap();
     }
 }
 
@@ -119,7 +109,7 @@ pub fn wait_any_key(ctx: &mut Context) {
     ctx.term.hide_cursor().unwrap();
     ctx.term
         .write_line(
-            "Press any key to continue..."
+            get_line!(ctx.lexicon, "interface.any-key")
                 .color(colors::INPUT)
                 .to_string()
                 .as_ref(),
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::entities::{class::Class, player::Player, Level, World};
+use crate::save::store_json;
+
+/// A single finished run.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Run {
+    pub level: u64,
+    pub xp: u64,
+    pub net_worth: u64,
+    pub caves: u64,
+    pub monsters: u64,
+    /// Seconds since the unix epoch at the time of death
+    pub timestamp: u64,
+    pub locale: String,
+    #[serde(default)]
+    pub seed: u64,
+    /// Id of the class the run was played as
+    #[serde(default = "Run::default_class")]
+    pub class: String,
+}
+
+impl Run {
+    pub fn new(world: &World, player: &Player, locale: &str, seed: u64) -> Self {
+        Self {
+            level: player.level(),
+            xp: player.xp(),
+            net_worth: player.net_worth(world),
+            caves: world.stats.caves,
+            monsters: world.stats.monsters,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            locale: locale.to_string(),
+            seed,
+            class: player.class().id.clone(),
+        }
+    }
+
+    fn default_class() -> String {
+        Class::default().id
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Level,
+    NetWorth,
+    Caves,
+    Monsters,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [
+        Metric::Level,
+        Metric::NetWorth,
+        Metric::Caves,
+        Metric::Monsters,
+    ];
+
+    /// Localization key for the metric's display name
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Self::Level => "leaderboard.metric.level",
+            Self::NetWorth => "leaderboard.metric.net-worth",
+            Self::Caves => "leaderboard.metric.caves",
+            Self::Monsters => "leaderboard.metric.monsters",
+        }
+    }
+
+    pub fn score(&self, run: &Run) -> u64 {
+        match *self {
+            // Break level ties with xp
+            Self::Level => run.xp,
+            Self::NetWorth => run.net_worth,
+            Self::Caves => run.caves,
+            Self::Monsters => run.monsters,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Leaderboard {
+    runs: Vec<Run>,
+}
+
+impl Leaderboard {
+    /// Loads the leaderboard from `file`. A missing file is treated as an
+    /// empty leaderboard.
+    pub fn load<P: AsRef<Path>>(file: P) -> Result<Self, Box<dyn Error>> {
+        match File::open(file) {
+            Ok(file) => Ok(serde_json::from_reader(file)?),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(Box::new(error)),
+        }
+    }
+
+    pub fn store<P: AsRef<Path>>(&self, file: P) -> Result<(), Box<dyn Error>> {
+        store_json(file, self)
+    }
+
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    /// Records a run and returns its index for highlighting.
+    pub fn record(&mut self, run: Run) -> usize {
+        self.runs.push(run);
+        self.runs.len() - 1
+    }
+
+    /// Returns `(index, run)` pairs ordered best first. Earlier runs win ties.
+    pub fn ranked(&self, metric: Metric) -> Vec<(usize, &Run)> {
+        let mut ranked: Vec<(usize, &Run)> = self.runs.iter().enumerate().collect();
+        ranked.sort_by_key(|(index, run)| (std::cmp::Reverse(metric.score(run)), *index));
+        ranked
+    }
+
+    /// The best `limit` runs as `(rank, index, run)`, rank 0 being the best,
+    /// plus the run at `current` wherever it ranks.
+    pub fn top(&self, metric: Metric, limit: usize, current: usize) -> Vec<(usize, usize, &Run)> {
+        self.ranked(metric)
+            .into_iter()
+            .enumerate()
+            .filter(|(rank, (index, _))| *rank < limit || *index == current)
+            .map(|(rank, (index, run))| (rank, index, run))
+            .collect()
+    }
+}
+
+/// Whether `error` from [`Leaderboard::load`] means the file is damaged, as
+/// opposed to it not being readable at all.
+pub fn is_corrupt(error: &(dyn Error + 'static)) -> bool {
+    error
+        .downcast_ref::<serde_json::Error>()
+        .is_some_and(|error| !error.is_io())
+}
+
+/// Moves a corrupt leaderboard out of the way so it isn't overwritten and
+/// can be recovered by hand.
+pub fn quarantine<P: AsRef<Path>>(file: P) {
+    let file = file.as_ref();
+    let _ = fs::rename(file, file.with_extension("corrupt"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(level: u64, xp: u64, caves: u64) -> Run {
+        Run {
+            level,
+            xp,
+            net_worth: 0,
+            caves,
+            monsters: 0,
+            timestamp: 0,
+            locale: "en-us".into(),
+            seed: 0,
+            class: Run::default_class(),
+        }
+    }
+
+    fn leaderboard(runs: Vec<Run>) -> Leaderboard {
+        let mut leaderboard = Leaderboard::default();
+        for run in runs {
+            leaderboard.record(run);
+        }
+        leaderboard
+    }
+
+    fn indices(ranked: Vec<(usize, &Run)>) -> Vec<usize> {
+        ranked.into_iter().map(|(index, _)| index).collect()
+    }
+
+    #[test]
+    fn ranks_best_first_and_earlier_runs_win_ties() {
+        let leaderboard = leaderboard(vec![run(1, 90, 4), run(2, 300, 7), run(1, 90, 7)]);
+        assert_eq!(indices(leaderboard.ranked(Metric::Caves)), vec![1, 2, 0]);
+        assert_eq!(indices(leaderboard.ranked(Metric::Level)), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn level_ties_are_broken_by_xp() {
+        let leaderboard = leaderboard(vec![run(3, 600, 0), run(3, 700, 0)]);
+        assert_eq!(indices(leaderboard.ranked(Metric::Level)), vec![1, 0]);
+    }
+
+    #[test]
+    fn top_runs_are_limited_but_keep_the_current_one() {
+        let leaderboard = leaderboard((0..5).map(|caves| run(0, 0, caves)).collect());
+        let top = |current| -> Vec<(usize, usize)> {
+            leaderboard
+                .top(Metric::Caves, 2, current)
+                .into_iter()
+                .map(|(rank, index, _)| (rank, index))
+                .collect()
+        };
+        assert_eq!(top(4), vec![(0, 4), (1, 3)]);
+        assert_eq!(top(0), vec![(0, 4), (1, 3), (4, 0)]);
+        assert!(leaderboard.top(Metric::Caves, 0, 99).is_empty());
+    }
+}
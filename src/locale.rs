@@ -0,0 +1,615 @@
+use colored::*;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::colors;
+use crate::entities::{class::Class, item::Item, monster::Monster, Cave};
+use crate::interface::GameRng;
+use crate::pack::Pack;
+
+/// Locale every other locale falls back to for lines it doesn't define.
+pub const FALLBACK_LOCALE: &str = "en-us";
+
+/// Environment variables checked for the user's locale, most specific first.
+const LOCALE_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Stream of the run's seed that flavor text variations are picked from, apart
+/// from the game's own rolls
+const TEXT_STREAM: u64 = 1;
+
+/// Turns a BCP-47 tag or POSIX locale name into the lowercase, hyphenated
+/// form locale files are named with, e.g. `en_US.UTF-8` becomes `en-us`.
+pub fn normalize(tag: &str) -> String {
+    tag.split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-")
+        .to_lowercase()
+}
+
+/// The locale asked for by the environment, if any.
+pub fn from_env() -> Option<String> {
+    LOCALE_VARS
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| normalize(&value))
+        .find(|locale| !locale.is_empty() && locale != "c" && locale != "posix")
+}
+
+/// Every locale any of `packs` has lines for, sorted and deduplicated.
+pub fn available(packs: &[Pack]) -> Vec<String> {
+    let mut locales: Vec<String> = packs.iter().flat_map(|pack| pack.locales()).collect();
+    locales.sort();
+    locales.dedup();
+    locales
+}
+
+/// Picks the available locale closest to `requested`: an exact match, then
+/// its language alone (`pt` for `pt-br`), then [`FALLBACK_LOCALE`] if it's the
+/// same language, then any locale of that language.
+pub fn negotiate(requested: &str, available: &[String]) -> Option<String> {
+    let requested = normalize(requested);
+    let language = requested.split('-').next().unwrap_or_default();
+    let is_available = |locale: &str| available.iter().any(|other| other == locale);
+    let same_language = |locale: &str| locale.split('-').next() == Some(language);
+
+    if is_available(&requested) {
+        Some(requested)
+    } else if is_available(language) {
+        Some(language.into())
+    } else if is_available(FALLBACK_LOCALE) && same_language(FALLBACK_LOCALE) {
+        Some(FALLBACK_LOCALE.into())
+    } else {
+        available
+            .iter()
+            .find(|locale| same_language(locale))
+            .cloned()
+    }
+}
+
+/// Locales whose lines make up `locale`, least specific first, so each one
+/// can override the lines of the ones before it.
+pub fn chain(locale: &str, available: &[String]) -> Vec<String> {
+    let mut chain = vec![FALLBACK_LOCALE.to_string()];
+    let mut tag = String::new();
+    for subtag in locale.split('-') {
+        if !tag.is_empty() {
+            tag.push('-');
+        }
+        tag.push_str(subtag);
+        if !chain.contains(&tag) && available.contains(&tag) {
+            chain.push(tag.clone());
+        }
+    }
+    chain
+}
+
+/// Loads `locale` from every pack into `lexicon`, on top of the locales it
+/// falls back to.
+pub fn load(packs: &[Pack], locale: &str, lexicon: &mut Lexicon) -> Result<(), Box<dyn Error>> {
+    for link in chain(locale, &available(packs)) {
+        for pack in packs {
+            pack.load_locale(&link, lexicon)?;
+        }
+    }
+    Ok(())
+}
+
+/// The flavor text RNG for a run started with `seed`.
+fn text_rng(seed: u64) -> GameRng {
+    let mut rng = GameRng::seed_from_u64(seed);
+    rng.set_stream(TEXT_STREAM);
+    rng
+}
+
+/// Fills in a `{{{name}}}` style template.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |line, (name, value)| {
+            line.replace(&format!("{{{{{{{}}}}}}}", name), value)
+        })
+}
+
+/// Plural categories, as in the Unicode CLDR plural rules.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Plural {
+    One,
+    Few,
+    Many,
+    Other,
+}
+
+impl Plural {
+    pub const ALL: [Self; 4] = [Self::One, Self::Few, Self::Many, Self::Other];
+
+    /// Rules a locale can pick with its `plural.rule` line.
+    pub const RULES: [&'static str; 5] = ["one-other", "other", "french", "slavic", "polish"];
+
+    /// Which category `count` falls into under `rule`.
+    pub fn of(rule: &str, count: u64) -> Self {
+        let (ones, tens) = (count % 10, count % 100);
+        let few = (2..=4).contains(&ones) && !(12..=14).contains(&tens);
+        match rule {
+            "other" => Self::Other,
+            "french" if count <= 1 => Self::One,
+            "slavic" if ones == 1 && tens != 11 => Self::One,
+            "slavic" if few => Self::Few,
+            "slavic" => Self::Many,
+            "polish" if count == 1 => Self::One,
+            "polish" if few => Self::Few,
+            "polish" => Self::Many,
+            "french" => Self::Other,
+            _ if count == 1 => Self::One,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::One => "one",
+            Self::Few => "few",
+            Self::Many => "many",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Every form of a name a line might need.
+pub struct Noun {
+    pub name: String,
+    pub plural: String,
+    /// Plural for the "few" category, in locales that have one
+    pub few: String,
+    /// Plural for the "many" category, in locales that have one
+    pub many: String,
+    /// With an indefinite article, e.g. "an Uncommon Orc"
+    pub indefinite: String,
+    /// With a definite article, e.g. "The Orc"
+    pub definite: String,
+    /// Grammatical gender as written in the locale files, or empty for
+    /// locales that don't need one
+    pub gender: String,
+}
+
+impl Noun {
+    /// The form to use alongside a number in `category`.
+    pub fn counted(&self, category: Plural) -> &str {
+        match category {
+            Plural::One => &self.name,
+            Plural::Few => &self.few,
+            Plural::Many => &self.many,
+            Plural::Other => &self.plural,
+        }
+    }
+}
+
+/// The loaded locale lines. Lines are printed through `get_line!` and
+/// `fmt_line!`, which pick one of a key's variations at random, and names and
+/// grammar are built from them.
+///
+/// Names are looked up as `monster.<id>` and `item.<id>`, falling back to the
+/// name in the content files, with optional `.plural`, `.gender`,
+/// `.indefinite` and `.definite` keys alongside. Everything else comes from the
+/// `grammar.*` and `rarity.*` keys.
+pub struct Lexicon {
+    lines: HashMap<String, Vec<String>>,
+    /// Picks between flavor text variations, seeded from the run so a replay
+    /// reads the same
+    rng: GameRng,
+}
+
+impl Default for Lexicon {
+    fn default() -> Self {
+        Self {
+            lines: HashMap::new(),
+            rng: text_rng(0),
+        }
+    }
+}
+
+impl Lexicon {
+    /// Restarts the flavor text RNG from the run's `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = text_rng(seed);
+    }
+
+    /// Adds the lines of a locale file, replacing any with the same key.
+    pub fn extend(&mut self, json: &str) -> Result<(), Box<dyn Error>> {
+        let lines: HashMap<String, Vec<String>> = serde_json::from_str(json)?;
+        self.lines.extend(lines);
+        Ok(())
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.lines.get(key).is_some_and(|lines| !lines.is_empty())
+    }
+
+    /// A variation of `key`, or the key itself if no locale defines it.
+    pub fn get(&mut self, key: &str) -> String {
+        let rng = &mut self.rng;
+        self.lines
+            .get(key)
+            .and_then(|lines| lines.choose(rng))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// A variation of `key` with its `{{{name}}}` placeholders filled in.
+    pub fn format(&mut self, key: &str, values: &[(&str, String)]) -> String {
+        let values: Vec<(&str, &str)> = values
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        fill(&self.get(key), &values)
+    }
+
+    fn line(&self, key: &str) -> Option<&str> {
+        self.lines.get(key)?.first().map(|line| line.as_str())
+    }
+
+    /// The first of `keys` the locale defines.
+    fn first_line<S: AsRef<str>>(&self, keys: &[S]) -> Option<&str> {
+        keys.iter().find_map(|key| self.line(key.as_ref()))
+    }
+
+    /// The plural category `count` falls into in this locale.
+    pub fn plural(&self, count: u64) -> Plural {
+        Plural::of(self.line("plural.rule").unwrap_or("one-other"), count)
+    }
+
+    /// `key` specialized for the plural category of `count`, e.g.
+    /// `count.cave.one`, falling back to its `other` form and then to `key`.
+    pub fn plural_key(&self, key: &str, count: u64) -> String {
+        [self.plural(count), Plural::Other]
+            .iter()
+            .map(|category| format!("{}.{}", key, category.key()))
+            .find(|key| self.has(key))
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Fills the right plural form of `key` with `count`, e.g. "3 caves".
+    pub fn count(&self, key: &str, count: u64) -> String {
+        let number = self.number(count);
+        match self.line(&self.plural_key(key, count)) {
+            Some(template) => fill(template, &[("count", &number)]),
+            None => number,
+        }
+    }
+
+    /// Formats a number with the locale's digit grouping and decimal mark.
+    pub fn number<N: Display>(&self, number: N) -> String {
+        let separator = self.line("number.group-separator").unwrap_or(",");
+        let decimal_mark = self.line("number.decimal-mark").unwrap_or(".");
+
+        let raw = number.to_string();
+        let (sign, raw) = match raw.strip_prefix('-') {
+            Some(raw) => ("-", raw),
+            None => ("", raw.as_str()),
+        };
+        let (whole, fraction) = match raw.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (raw, None),
+        };
+
+        let mut output = sign.to_string();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                output.push_str(separator);
+            }
+            output.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            output.push_str(decimal_mark);
+            output.push_str(fraction);
+        }
+        output
+    }
+
+    /// `key` specialized for `gender` if the locale has a line for it, so
+    /// templates can agree with the names they're filled with.
+    pub fn gendered_key(&self, key: &str, gender: &str) -> String {
+        let gendered = format!("{}.{}", key, gender);
+        if !gender.is_empty() && self.has(&gendered) {
+            gendered
+        } else {
+            key.to_string()
+        }
+    }
+
+    fn starts_with_vowel(&self, word: &str) -> bool {
+        let vowels = self.line("grammar.vowels").unwrap_or("aeiou");
+        word.chars()
+            .next()
+            .is_some_and(|first| vowels.contains(first.to_lowercase().next().unwrap_or(first)))
+    }
+
+    /// Wraps a name in the article template named `form` ("indefinite" or
+    /// "definite"), preferring a template specific to this name, then to its
+    /// gender, then to words starting with a vowel.
+    fn with_article(
+        &self,
+        prefix: &str,
+        form: &str,
+        name: &str,
+        colored: &str,
+        gender: &str,
+    ) -> String {
+        let mut keys = vec![format!("{}.{}", prefix, form)];
+        let mut general = Vec::new();
+        if !gender.is_empty() {
+            general.push(format!("grammar.{}.{}", form, gender));
+        }
+        general.push(format!("grammar.{}", form));
+        if self.starts_with_vowel(name) {
+            keys.extend(general.iter().map(|key| format!("{}.vowel", key)));
+        }
+        keys.extend(general);
+
+        match self.first_line(&keys) {
+            Some(template) => fill(template, &[("name", colored)]),
+            None => colored.to_string(),
+        }
+    }
+
+    fn noun(
+        &self,
+        prefix: &str,
+        raw_name: &str,
+        rarity: Option<&str>,
+        proper_noun: bool,
+        color: Color,
+    ) -> Noun {
+        let name = self.line(prefix).unwrap_or(raw_name);
+        let gender = self
+            .first_line(&[format!("{}.gender", prefix), "grammar.gender".into()])
+            .unwrap_or_default()
+            .to_string();
+        let plural = match self.line(&format!("{}.plural", prefix)) {
+            Some(plural) => plural.to_string(),
+            None => fill(
+                self.line("grammar.plural").unwrap_or("{{{name}}}"),
+                &[("name", name)],
+            ),
+        };
+        let form = |category: Plural| {
+            self.line(&format!("{}.{}", prefix, category.key()))
+                .map(|form| form.to_string())
+                .unwrap_or_else(|| plural.clone())
+        };
+        let (few, many) = (form(Plural::Few), form(Plural::Many));
+
+        // Generic monsters carry their rarity in their name
+        let (name, plural, few, many) = match rarity {
+            Some(rarity) => {
+                let rarity_key = format!("rarity.{}", rarity);
+                let gendered_key = format!("{}.{}", rarity_key, gender);
+                let adjective = self
+                    .first_line(&[&gendered_key, &rarity_key])
+                    .unwrap_or(rarity);
+                let plural_adjective = self
+                    .first_line(&[
+                        format!("{}.plural", gendered_key),
+                        format!("{}.plural", rarity_key),
+                    ])
+                    .unwrap_or(adjective);
+                let template = self
+                    .line("grammar.rarity")
+                    .unwrap_or("{{{rarity}}} {{{name}}}");
+                let plural_of =
+                    |name: &str| fill(template, &[("rarity", plural_adjective), ("name", name)]);
+                (
+                    fill(template, &[("rarity", adjective), ("name", name)]),
+                    plural_of(&plural),
+                    plural_of(&few),
+                    plural_of(&many),
+                )
+            }
+            None => (name.to_string(), plural, few, many),
+        };
+
+        let colored = name.color(color).to_string();
+        let (indefinite, definite) = if proper_noun {
+            (colored.clone(), colored.clone())
+        } else {
+            (
+                self.with_article(prefix, "indefinite", &name, &colored, &gender),
+                self.with_article(prefix, "definite", &name, &colored, &gender),
+            )
+        };
+        Noun {
+            name: colored,
+            plural: plural.color(color).to_string(),
+            few: few.color(color).to_string(),
+            many: many.color(color).to_string(),
+            indefinite,
+            definite,
+            gender,
+        }
+    }
+
+    pub fn monster(&self, monster: &Monster) -> Noun {
+        let mut noun = self.noun(
+            &format!("monster.{}", monster.id()),
+            monster.raw_name(),
+            if monster.generic() {
+                Some(monster.rarity().id())
+            } else {
+                None
+            },
+            monster.proper_noun(),
+            colors::MONSTER,
+        );
+        // One of a kind monsters are never "a" anything
+        if !monster.generic() {
+            noun.indefinite = noun.definite.clone();
+        }
+        noun
+    }
+
+    /// The cave's name, translated by a `cave.<theme>` line if the locale
+    /// has one.
+    pub fn cave(&self, cave: &Cave) -> String {
+        self.line(&format!("cave.{}", cave.theme))
+            .unwrap_or(&cave.name)
+            .to_string()
+    }
+
+    /// The class's name, translated by a `class.<id>` line if the locale has
+    /// one.
+    pub fn class(&self, class: &Class) -> String {
+        self.line(&format!("class.{}", class.id))
+            .unwrap_or(&class.name)
+            .to_string()
+    }
+
+    /// Like [`Lexicon::class`], for the class's description.
+    pub fn class_description(&self, class: &Class) -> String {
+        self.line(&format!("class.{}.description", class.id))
+            .unwrap_or(&class.description)
+            .to_string()
+    }
+
+    pub fn item(&self, item: &Item) -> Noun {
+        self.noun(
+            &format!("item.{}", item.id()),
+            item.raw_name(),
+            None,
+            false,
+            colors::ITEM,
+        )
+    }
+}
+
+/// A line from a `Lexicon`, e.g. `get_line!(ctx.lexicon, "interface.quit")`.
+macro_rules! get_line {
+    ($lexicon:expr, $key:expr) => {
+        $lexicon.get($key)
+    };
+}
+pub(crate) use get_line;
+
+/// A line from a `Lexicon` with its placeholders filled in, e.g.
+/// `fmt_line!(ctx.lexicon, "merchant.bought", item = name)`.
+macro_rules! fmt_line {
+    ($lexicon:expr, $key:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let values = [$((stringify!($name), $value.to_string())),+];
+        $lexicon.format($key, &values)
+    }};
+}
+pub(crate) use fmt_line;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locales(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn categories(rule: &str, counts: &[u64]) -> Vec<&'static str> {
+        counts
+            .iter()
+            .map(|count| Plural::of(rule, *count).key())
+            .collect()
+    }
+
+    #[test]
+    fn picks_plural_categories_by_rule() {
+        let counts = [0, 1, 2, 5, 11, 12, 21, 22, 25, 112];
+        assert_eq!(
+            categories("one-other", &counts),
+            [
+                "other", "one", "other", "other", "other", "other", "other", "other", "other",
+                "other"
+            ]
+        );
+        assert_eq!(categories("other", &[0, 1, 2]), ["other"; 3]);
+        assert_eq!(categories("french", &[0, 1, 2]), ["one", "one", "other"]);
+        assert_eq!(
+            categories("slavic", &counts),
+            ["many", "one", "few", "many", "many", "many", "one", "few", "many", "many"]
+        );
+        assert_eq!(
+            categories("polish", &counts),
+            ["many", "one", "few", "many", "many", "many", "many", "few", "many", "many"]
+        );
+    }
+
+    #[test]
+    fn counts_use_the_locale_plural_forms() {
+        let mut lexicon = Lexicon::default();
+        lexicon
+            .extend(
+                r#"{
+                    "plural.rule": ["slavic"],
+                    "count.cave.one": ["{{{count}}} jaskinia"],
+                    "count.cave.few": ["{{{count}}} jaskinie"],
+                    "count.cave.other": ["{{{count}}} jaskiń"]
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(lexicon.count("count.cave", 1), "1 jaskinia");
+        assert_eq!(lexicon.count("count.cave", 3), "3 jaskinie");
+        // No "many" form, so it falls back to "other"
+        assert_eq!(lexicon.count("count.cave", 5), "5 jaskiń");
+        assert_eq!(lexicon.count("count.monster", 5), "5");
+    }
+
+    #[test]
+    fn formats_numbers_with_the_locale_separators() {
+        let mut lexicon = Lexicon::default();
+        assert_eq!(lexicon.number(0), "0");
+        assert_eq!(lexicon.number(999), "999");
+        assert_eq!(lexicon.number(1234567), "1,234,567");
+        assert_eq!(lexicon.number(-1234), "-1,234");
+        lexicon
+            .extend(r#"{ "number.group-separator": ["."], "number.decimal-mark": [","] }"#)
+            .unwrap();
+        assert_eq!(lexicon.number(1234567), "1.234.567");
+        assert_eq!(lexicon.number(1234.5), "1.234,5");
+    }
+
+    #[test]
+    fn normalizes_posix_and_bcp_47_names() {
+        assert_eq!(normalize("en_US.UTF-8"), "en-us");
+        assert_eq!(normalize("de_DE@euro"), "de-de");
+        assert_eq!(normalize("pt-BR"), "pt-br");
+        assert_eq!(normalize(" fr "), "fr");
+    }
+
+    #[test]
+    fn negotiates_the_closest_locale() {
+        let available = locales(&["de", "en-pr", "en-us", "pt-br"]);
+        assert_eq!(negotiate("en_US.UTF-8", &available), Some("en-us".into()));
+        assert_eq!(negotiate("en-PR", &available), Some("en-pr".into()));
+        assert_eq!(negotiate("de-AT", &available), Some("de".into()));
+        assert_eq!(negotiate("pt-PT", &available), Some("pt-br".into()));
+        assert_eq!(negotiate("ja-JP", &available), None);
+    }
+
+    #[test]
+    fn prefers_the_fallback_locale_within_its_language() {
+        let available = locales(&["en-pr", "en-us"]);
+        assert_eq!(negotiate("en_GB.UTF-8", &available), Some("en-us".into()));
+        assert_eq!(negotiate("en", &available), Some("en-us".into()));
+        assert_eq!(
+            negotiate("en-GB", &locales(&["en-au", "en-pr"])),
+            Some("en-au".into())
+        );
+    }
+
+    #[test]
+    fn chains_from_the_fallback_to_the_most_specific_locale() {
+        let available = locales(&["en-us", "pt", "pt-br", "zh-hant-tw"]);
+        assert_eq!(chain("pt-br", &available), vec!["en-us", "pt", "pt-br"]);
+        assert_eq!(chain("pt-pt", &available), vec!["en-us", "pt"]);
+        assert_eq!(chain("en-us", &available), vec!["en-us"]);
+        assert_eq!(chain("zh-hant-tw", &available), vec!["en-us", "zh-hant-tw"]);
+    }
+}
@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::path::{Path, PathBuf};
 
 use clap::Clap;
 
@@ -18,48 +19,173 @@ pub mod colors {
 
     pub const ITEM: Color = Color::BrightBlue;
     pub const MONSTER: Color = Color::BrightYellow;
+
+    pub const HIGHLIGHT: Color = Color::BrightGreen;
 }
+pub mod engine;
 pub mod entities;
 pub mod game;
 pub mod interface;
+pub mod leaderboard;
+pub mod locale;
+pub mod pack;
+pub mod save;
+pub mod simulate;
 
 use entities::{player::Player, World};
+use locale::get_line;
+use pack::Pack;
+use save::SaveFile;
+
+/// Content loaded on top of the built-in content when `--data-dir` isn't given.
+const DEFAULT_DATA_DIR: &str = "./data";
+
+#[derive(Clap)]
+enum Command {
+    /// Check the content and localization files for mistakes
+    Validate,
+}
 
 #[derive(Clap)]
 #[clap(version = "0.1.0", author = "Jake Ledoux <me@jakeledoux.com>")]
 struct Opts {
-    #[clap(short, long, default_value = "en-US", possible_values = &["en-US", "en-PR"])]
-    locale: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// Directory holding the base game content [default: ./data, if present]
+    #[clap(long, parse(from_os_str))]
+    data_dir: Option<PathBuf>,
+    /// Extra content pack to layer over the base content; may be repeated
+    #[clap(
+        long,
+        parse(from_os_str),
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pack: Vec<PathBuf>,
+    /// Language to play in, e.g. en-US [default: from LC_ALL or LANG]
+    #[clap(short, long)]
+    locale: Option<String>,
+    /// File to autosave the current run to after every cave
+    #[clap(long, parse(from_os_str))]
+    save: Option<PathBuf>,
+    /// Saved run to resume
+    #[clap(long, parse(from_os_str))]
+    load: Option<PathBuf>,
+    /// File finished runs are recorded to
+    #[clap(long, parse(from_os_str), default_value = "leaderboard.json")]
+    leaderboard: PathBuf,
+    /// Let fights play out on their own instead of asking what to do each
+    /// round
+    #[clap(long)]
+    auto_battle: bool,
+    /// Seed for the first run, to replay it exactly
+    #[clap(long)]
+    seed: Option<u64>,
+    /// Play this many runs without a terminal and print balance statistics
+    #[clap(long)]
+    simulate: Option<u64>,
+    /// How simulated players pick caves
+    #[clap(long, default_value = "random", possible_values = &["first", "easiest", "random"])]
+    policy: String,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let opts = Opts::parse();
 
-    let mut ctx = interface::Context::default();
-    ctx.hottext
-        .load_json(format!("./data/localization/{}.json", opts.locale))
-        .expect("No localization file exists for given locale.");
+    let packs = load_packs(&opts)?;
+    if let Some(Command::Validate) = opts.command {
+        return validate(&packs);
+    }
+
+    let mut world = World::new();
+    for pack in &packs {
+        pack.load(&mut world)?;
+    }
 
-    let mut world = World::new()
-        .with_load_monsters("./data/monsters/generic.json")?
-        .with_load_monsters("./data/monsters/unique.json")?
-        .with_load_items("./data/items/armor.json")?
-        .with_load_items("./data/items/shields.json")?
-        .with_load_items("./data/items/collectibles.json")?
-        .with_load_items("./data/items/potions.json")?
-        .with_load_items("./data/items/weapons.json")?;
+    if let Some(runs) = opts.simulate {
+        let mut policy = simulate::policy(&opts.policy);
+        let report = simulate::simulate(
+            &mut world,
+            policy.as_mut(),
+            runs,
+            opts.seed.unwrap_or_else(rand::random),
+        );
+        println!("{}", report);
+        return Ok(());
+    }
+
+    let mut ctx = interface::Context {
+        auto_battle: opts.auto_battle,
+        ..interface::Context::default()
+    };
+    let locales = locale::available(&packs);
+    if !locales
+        .iter()
+        .any(|locale| locale == locale::FALLBACK_LOCALE)
+    {
+        return Err(format!("No {} localization file found", locale::FALLBACK_LOCALE).into());
+    }
+    let requested = opts.locale.clone().or_else(locale::from_env);
+    let locale = match requested
+        .as_deref()
+        .map(|tag| locale::negotiate(tag, &locales))
+    {
+        Some(Some(locale)) => locale,
+        Some(None) if opts.locale.is_some() => {
+            eprintln!(
+                "Locale {} isn't available (try one of {}), falling back to {}.",
+                requested.unwrap_or_default(),
+                locales.join(", "),
+                locale::FALLBACK_LOCALE
+            );
+            locale::FALLBACK_LOCALE.into()
+        }
+        _ => locale::FALLBACK_LOCALE.into(),
+    };
+    locale::load(&packs, &locale, &mut ctx.lexicon)?;
+
+    // Look for a run to resume
+    let mut resume = if let Some(load) = &opts.load {
+        Some(SaveFile::load(load)?)
+    } else {
+        match &opts.save {
+            Some(save) if save.exists() => {
+                let prompt = get_line!(ctx.lexicon, "save.resume-prompt");
+                let choices: [&str; 2] = [
+                    &get_line!(ctx.lexicon, "save.resume"),
+                    &get_line!(ctx.lexicon, "save.new-game"),
+                ];
+                match interface::get_choice(&mut ctx, &prompt, &choices) {
+                    0 => Some(SaveFile::load(save)?),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    };
+    let save_path = opts.save.or(opts.load);
+    let mut seed = opts.seed;
 
     loop {
         // Reset world and player for a new game
         world.reset();
-        let mut player = Player::default();
+        let mut player = match resume.take() {
+            Some(save) => save.restore(&mut world, &mut ctx),
+            None => {
+                ctx.reseed(seed.take().unwrap_or_else(rand::random));
+                let class = game::choose_class(&world, &mut ctx);
+                Player::new(&class, &world)
+            }
+        };
 
         loop {
             let result = game::enter_cave(&mut world, &mut player, &mut ctx);
-            if let game::CaveResult::Survived { reward } = result {
-                if game::show_cave_reward(&mut world, &mut player, reward, &mut ctx)
-                    .is_show_status_report()
-                {
+            if let engine::CaveResult::Survived { reward } = result {
+                let choice = game::show_cave_reward(&mut world, &mut player, reward, &mut ctx);
+                if let Some(save_path) = &save_path {
+                    game::autosave(save_path, &world, &player, &mut ctx);
+                }
+                if choice.is_show_status_report() {
                     game::show_status(&world, &player, &mut ctx);
                 }
             } else {
@@ -67,11 +193,69 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         }
 
+        // Dead runs can't be resumed
+        if let Some(save_path) = &save_path {
+            save::discard(save_path);
+        }
+
         // Game over
-        if game::show_death_screen(&world, &player, &mut ctx).is_quit() {
+        let (leaderboard, current_run) =
+            game::record_run(&opts.leaderboard, &world, &player, &locale, &mut ctx);
+        if game::show_death_screen(&world, &player, &leaderboard, current_run, &mut ctx).is_quit() {
             break;
         }
     }
 
     Ok(())
 }
+
+/// The base content followed by every extra pack, in the order they should be
+/// loaded.
+fn load_packs(opts: &Opts) -> Result<Vec<Pack>, Box<dyn Error>> {
+    let mut packs = Vec::new();
+    #[cfg(feature = "embedded")]
+    packs.push(Pack::embedded());
+    match &opts.data_dir {
+        Some(dir) => packs.push(Pack::open(dir)?),
+        None if Path::new(DEFAULT_DATA_DIR).is_dir() => packs.push(Pack::open(DEFAULT_DATA_DIR)?),
+        None => {}
+    }
+    if packs.is_empty() {
+        return Err("No game content found; point --data-dir at it".into());
+    }
+    for dir in &opts.pack {
+        packs.push(Pack::open(dir)?);
+    }
+    Ok(pack::sort(packs)?)
+}
+
+fn validate(packs: &[Pack]) -> Result<(), Box<dyn Error>> {
+    // Packs are checked one at a time, since overriding an id from an earlier
+    // pack is intended. Their references can still point into earlier packs.
+    let mut diagnostics = Vec::new();
+    let mut loaded = World::new();
+    for pack in packs {
+        diagnostics.extend(loaded.validate(
+            &pack.item_files()?,
+            &pack.monster_files()?,
+            &pack.ability_files()?,
+            &pack.boss_files()?,
+            &pack.class_files()?,
+            &pack.theme_files()?,
+            &pack.loot_files()?,
+            &pack.locale_files()?,
+        ));
+        // Anything that keeps the pack from loading was reported above
+        let _ = pack.load(&mut loaded);
+    }
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if diagnostics.is_empty() {
+        println!("All content files are valid.");
+        Ok(())
+    } else {
+        eprintln!("Found {} problem(s).", diagnostics.len());
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,412 @@
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::entities::World;
+use crate::locale::{self, Lexicon};
+
+/// Optional file at the root of a pack describing it.
+pub const MANIFEST_FILE: &str = "pack.json";
+
+#[derive(Debug)]
+pub enum PackError {
+    MissingDependency { pack: String, dependency: String },
+    Cycle { packs: Vec<String> },
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingDependency { pack, dependency } => write!(
+                f,
+                "Pack \"{}\" depends on \"{}\", which isn't loaded",
+                pack, dependency
+            ),
+            Self::Cycle { packs } => write!(
+                f,
+                "Packs {} depend on each other",
+                packs
+                    .iter()
+                    .map(|name| format!("\"{}\"", name))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for PackError {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Packs with a lower load order are loaded first, so higher ones win
+    /// when both define the same id.
+    #[serde(default)]
+    pub load_order: i32,
+    /// Names of packs that must be loaded before this one.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// The default content, compiled in so the game runs from anywhere.
+#[cfg(feature = "embedded")]
+mod embedded {
+    pub const MANIFEST: &str = include_str!("../data/pack.json");
+    pub const MONSTERS: [&str; 2] = [
+        include_str!("../data/monsters/generic.json"),
+        include_str!("../data/monsters/unique.json"),
+    ];
+    pub const ITEMS: [&str; 5] = [
+        include_str!("../data/items/armor.json"),
+        include_str!("../data/items/collectibles.json"),
+        include_str!("../data/items/potions.json"),
+        include_str!("../data/items/shields.json"),
+        include_str!("../data/items/weapons.json"),
+    ];
+    pub const CAVES: [&str; 1] = [include_str!("../data/caves/caves.json")];
+    pub const LOOT: [&str; 1] = [include_str!("../data/loot/tables.json")];
+    pub const ABILITIES: [&str; 1] = [include_str!("../data/abilities/abilities.json")];
+    pub const BOSSES: [&str; 1] = [include_str!("../data/bosses/bosses.json")];
+    pub const CLASSES: [&str; 1] = [include_str!("../data/classes/classes.json")];
+    pub const LOCALES: [(&str, &str); 2] = [
+        ("en-pr", include_str!("../data/localization/en-pr.json")),
+        ("en-us", include_str!("../data/localization/en-us.json")),
+    ];
+}
+
+/// A directory of content laid out like `./data`: any number of JSON files
+/// under `monsters/`, `abilities/`, `bosses/`, `classes/`, `items/`, `caves/`
+/// and `loot/`, and locale files under `localization/`.
+pub struct Pack {
+    /// Where the pack lives on disk, or `None` for the built-in content, which
+    /// has no files.
+    pub dir: Option<PathBuf>,
+    pub manifest: Manifest,
+}
+
+impl Pack {
+    /// The default content the binary was built with.
+    #[cfg(feature = "embedded")]
+    pub fn embedded() -> Self {
+        Self {
+            dir: None,
+            manifest: serde_json::from_str(embedded::MANIFEST)
+                .expect("Built-in pack manifest is invalid."),
+        }
+    }
+
+    /// Opens the pack at `dir`. A pack without a manifest is named after its
+    /// directory.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Box<dyn Error>> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            serde_json::from_reader(File::open(manifest_path)?)?
+        } else {
+            Manifest {
+                name: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| dir.to_string_lossy().into_owned()),
+                version: None,
+                load_order: 0,
+                dependencies: Vec::new(),
+            }
+        };
+        Ok(Self {
+            dir: Some(dir),
+            manifest,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    /// The pack's ability files.
+    pub fn ability_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("abilities")
+    }
+
+    /// The pack's boss files.
+    pub fn boss_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("bosses")
+    }
+
+    /// The pack's character class files.
+    pub fn class_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("classes")
+    }
+
+    /// The pack's item files.
+    pub fn item_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("items")
+    }
+
+    /// The pack's monster files.
+    pub fn monster_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("monsters")
+    }
+
+    /// The pack's cave theme files.
+    pub fn theme_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("caves")
+    }
+
+    /// The pack's loot table files.
+    pub fn loot_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("loot")
+    }
+
+    /// The pack's locale files.
+    pub fn locale_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.files("localization")
+    }
+
+    fn files(&self, subdir: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        match &self.dir {
+            Some(dir) => json_files(dir.join(subdir)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Adds the pack's items, monsters, abilities, bosses, classes, cave themes
+    /// and loot tables to `world`, replacing any already loaded with the same
+    /// id.
+    pub fn load(&self, world: &mut World) -> Result<(), Box<dyn Error>> {
+        if self.dir.is_none() {
+            #[cfg(feature = "embedded")]
+            {
+                for json in embedded::ABILITIES.iter() {
+                    world.load_abilities_from(json.as_bytes())?;
+                }
+                for json in embedded::MONSTERS.iter() {
+                    world.load_monsters_from(json.as_bytes())?;
+                }
+                for json in embedded::BOSSES.iter() {
+                    world.load_bosses_from(json.as_bytes())?;
+                }
+                for json in embedded::CLASSES.iter() {
+                    world.load_classes_from(json.as_bytes())?;
+                }
+                for json in embedded::ITEMS.iter() {
+                    world.load_items_from(json.as_bytes())?;
+                }
+                for json in embedded::CAVES.iter() {
+                    world.load_themes_from(json.as_bytes())?;
+                }
+                for json in embedded::LOOT.iter() {
+                    world.load_loot_tables_from(json.as_bytes())?;
+                }
+            }
+            return Ok(());
+        }
+
+        for file in self.ability_files()? {
+            world.load_abilities(file)?;
+        }
+        for file in self.monster_files()? {
+            world.load_monsters(file)?;
+        }
+        for file in self.boss_files()? {
+            world.load_bosses(file)?;
+        }
+        for file in self.class_files()? {
+            world.load_classes(file)?;
+        }
+        for file in self.item_files()? {
+            world.load_items(file)?;
+        }
+        for file in self.theme_files()? {
+            world.load_themes(file)?;
+        }
+        for file in self.loot_files()? {
+            world.load_loot_tables(file)?;
+        }
+        Ok(())
+    }
+
+    /// Normalized names of the locales the pack has lines for.
+    pub fn locales(&self) -> Vec<String> {
+        if self.dir.is_none() {
+            #[cfg(feature = "embedded")]
+            return embedded::LOCALES
+                .iter()
+                .map(|(name, _)| locale::normalize(name))
+                .collect();
+            #[cfg(not(feature = "embedded"))]
+            return Vec::new();
+        }
+
+        self.locale_files()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|path| path.file_stem())
+            .map(|stem| locale::normalize(&stem.to_string_lossy()))
+            .collect()
+    }
+
+    /// Adds the pack's lines for `locale` to `lexicon`. Locale names are
+    /// compared normalized, so `en-US` finds `en-us.json`. Returns whether the
+    /// pack had the locale.
+    pub fn load_locale(&self, locale: &str, lexicon: &mut Lexicon) -> Result<bool, Box<dyn Error>> {
+        if self.dir.is_none() {
+            #[cfg(feature = "embedded")]
+            {
+                if let Some((_, json)) = embedded::LOCALES
+                    .iter()
+                    .find(|(name, _)| locale::normalize(name) == locale::normalize(locale))
+                {
+                    lexicon.extend(json)?;
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+
+        let file = self.locale_files()?.into_iter().find(|path| {
+            path.file_stem().is_some_and(|stem| {
+                locale::normalize(&stem.to_string_lossy()) == locale::normalize(locale)
+            })
+        });
+        match file {
+            Some(file) => {
+                lexicon.extend(&std::fs::read_to_string(&file)?)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// All `*.json` files directly inside `dir`, sorted so packs load the same way
+/// on every platform. A missing directory just has no files.
+fn json_files(dir: PathBuf) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Orders `packs` so that every pack comes after its dependencies, otherwise
+/// by load order and then by the order they were given in.
+pub fn sort(mut packs: Vec<Pack>) -> Result<Vec<Pack>, PackError> {
+    for pack in &packs {
+        for dependency in &pack.manifest.dependencies {
+            if !packs.iter().any(|other| other.name() == dependency) {
+                return Err(PackError::MissingDependency {
+                    pack: pack.name().into(),
+                    dependency: dependency.clone(),
+                });
+            }
+        }
+    }
+
+    packs.sort_by_key(|pack| pack.manifest.load_order);
+    let mut sorted: Vec<Pack> = Vec::with_capacity(packs.len());
+    while !packs.is_empty() {
+        let ready = packs.iter().position(|pack| {
+            pack.manifest
+                .dependencies
+                .iter()
+                .all(|dependency| sorted.iter().any(|loaded| loaded.name() == dependency))
+        });
+        match ready {
+            Some(index) => sorted.push(packs.remove(index)),
+            None => {
+                return Err(PackError::Cycle {
+                    packs: packs.iter().map(|pack| pack.name().into()).collect(),
+                })
+            }
+        }
+    }
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(name: &str, load_order: i32, dependencies: &[&str]) -> Pack {
+        Pack {
+            dir: Some(PathBuf::from(name)),
+            manifest: Manifest {
+                name: name.into(),
+                version: None,
+                load_order,
+                dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+            },
+        }
+    }
+
+    fn names(packs: Vec<Pack>) -> Vec<String> {
+        packs.iter().map(|pack| pack.name().to_string()).collect()
+    }
+
+    #[test]
+    fn sorts_by_load_order_then_given_order() {
+        let packs = vec![
+            pack("late", 5, &[]),
+            pack("first", 0, &[]),
+            pack("second", 0, &[]),
+            pack("early", -1, &[]),
+        ];
+        assert_eq!(
+            names(sort(packs).unwrap()),
+            vec!["early", "first", "second", "late"]
+        );
+    }
+
+    #[test]
+    fn dependencies_load_first_whatever_their_load_order() {
+        let packs = vec![
+            pack("mod", -10, &["base"]),
+            pack("base", 10, &[]),
+            pack("addon", 0, &["mod"]),
+        ];
+        assert_eq!(names(sort(packs).unwrap()), vec!["base", "mod", "addon"]);
+    }
+
+    #[test]
+    fn missing_dependencies_are_an_error() {
+        let packs = vec![pack("base", 0, &[]), pack("mod", 0, &["extras"])];
+        match sort(packs) {
+            Err(PackError::MissingDependency { pack, dependency }) => {
+                assert_eq!((pack.as_str(), dependency.as_str()), ("mod", "extras"));
+            }
+            _ => panic!("expected a missing dependency"),
+        }
+    }
+
+    #[test]
+    fn dependency_cycles_are_an_error() {
+        let packs = vec![
+            pack("base", 0, &[]),
+            pack("a", 0, &["b"]),
+            pack("b", 0, &["a"]),
+        ];
+        match sort(packs) {
+            Err(PackError::Cycle { packs }) => assert_eq!(packs, vec!["a", "b"]),
+            _ => panic!("expected a cycle"),
+        }
+    }
+}
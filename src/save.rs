@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+
+use crate::entities::{player::Player, Stats, World};
+use crate::interface::{Context, GameRng};
+
+/// Bumped whenever the layout of [`SaveFile`] changes in a way older builds
+/// can't read.
+pub const SAVE_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub struct SaveVersionError {
+    pub found: u32,
+}
+
+impl fmt::Display for SaveVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Save file version {} is not supported (expected {})",
+            self.found, SAVE_VERSION
+        )
+    }
+}
+
+impl Error for SaveVersionError {}
+
+/// Everything needed to pick a run back up where it was left off.
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub player: Player,
+    pub stats: Stats,
+    pub seed: u64,
+    pub rng: GameRng,
+}
+
+impl SaveFile {
+    pub fn new(world: &World, player: &Player, ctx: &Context) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            player: player.clone(),
+            stats: world.stats.clone(),
+            seed: ctx.seed,
+            rng: ctx.rng.clone(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(file: P) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(file)?;
+        let save: Self = serde_json::from_reader(file)?;
+        if save.version != SAVE_VERSION {
+            return Err(Box::new(SaveVersionError {
+                found: save.version,
+            }));
+        }
+        Ok(save)
+    }
+
+    pub fn store<P: AsRef<Path>>(&self, file: P) -> Result<(), Box<dyn Error>> {
+        store_json(file, self)
+    }
+
+    /// Restores the saved run into `world` and `ctx` and returns the saved
+    /// player.
+    pub fn restore(self, world: &mut World, ctx: &mut Context) -> Player {
+        world.stats = self.stats;
+        ctx.seed = self.seed;
+        ctx.rng = self.rng;
+        ctx.lexicon.reseed(self.seed);
+        let mut player = self.player;
+        player.forget_unknown_items(world);
+        player
+    }
+}
+
+/// Writes `value` to a temporary file first so that a crash mid-write never
+/// clobbers the previous contents of `file`.
+pub fn store_json<P, T>(file: P, value: &T) -> Result<(), Box<dyn Error>>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+{
+    let file = file.as_ref();
+    let temp_file = file.with_extension("tmp");
+    serde_json::to_writer(File::create(&temp_file)?, value)?;
+    fs::rename(temp_file, file)?;
+    Ok(())
+}
+
+/// Deletes the save for a finished run so it isn't offered for resuming.
+pub fn discard<P: AsRef<Path>>(file: P) {
+    let _ = fs::remove_file(file);
+}
@@ -0,0 +1,210 @@
+use rand::prelude::*;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::engine::{self, CaveResult, Clue, Silent};
+use crate::entities::{player::Player, Cave, CaveDifficulty, Level, Rarity, World};
+use crate::interface::GameRng;
+
+/// Runs are cut off after this many caves in case a policy never dies
+const MAX_CAVES: u64 = 1_000;
+/// Caves survived at which gold and xp are sampled
+const CURVE_POINTS: [u64; 8] = [1, 5, 10, 25, 50, 100, 250, 500];
+
+/// Decides which cave a simulated player enters. `clues` holds what the
+/// player made out about each of the `caves`.
+pub trait Policy {
+    fn choose_cave(&mut self, caves: &[Cave], clues: &[Vec<Clue>], rng: &mut GameRng) -> usize;
+}
+
+/// Always enters the first cave offered.
+pub struct FirstCave;
+
+impl Policy for FirstCave {
+    fn choose_cave(&mut self, _caves: &[Cave], _clues: &[Vec<Clue>], _rng: &mut GameRng) -> usize {
+        0
+    }
+}
+
+/// Enters the cave that looks easiest from its clues, the way a careful
+/// player would. Misread clues mislead it just the same.
+pub struct EasiestCave;
+
+impl EasiestCave {
+    /// How dangerous `clue` makes a cave look
+    fn danger(clue: &Clue) -> u64 {
+        match clue {
+            Clue::Silence => 0,
+            Clue::Sounds { difficulty } => match difficulty {
+                CaveDifficulty::Easy => 0,
+                CaveDifficulty::Hard => Rarity::Legendary as u64 + 1,
+            },
+            Clue::Smell { monster } | Clue::Glimpse { monster } => monster.rarity() as u64 + 1,
+            Clue::Tracks { monster, many } => {
+                (monster.rarity() as u64 + 1) * if *many { 2 } else { 1 }
+            }
+        }
+    }
+}
+
+impl Policy for EasiestCave {
+    fn choose_cave(&mut self, _caves: &[Cave], clues: &[Vec<Clue>], _rng: &mut GameRng) -> usize {
+        clues
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, clues)| clues.iter().map(Self::danger).sum::<u64>())
+            .map(|(index, _)| index)
+            .unwrap_or_default()
+    }
+}
+
+/// Enters a cave at random, like a player without any clues would.
+pub struct RandomCave;
+
+impl Policy for RandomCave {
+    fn choose_cave(&mut self, caves: &[Cave], _clues: &[Vec<Clue>], rng: &mut GameRng) -> usize {
+        rng.gen_range(0..caves.len())
+    }
+}
+
+pub fn policy(name: &str) -> Box<dyn Policy> {
+    match name {
+        "first" => Box::new(FirstCave),
+        "easiest" => Box::new(EasiestCave),
+        _ => Box::new(RandomCave),
+    }
+}
+
+pub struct RunSummary {
+    pub caves: u64,
+    pub level: u64,
+    pub gold: u64,
+    pub xp: u64,
+    /// Whether the run was cut off at [`MAX_CAVES`] rather than ending in death
+    pub capped: bool,
+    /// `(caves survived, gold, xp)` at each of [`CURVE_POINTS`] the run reached
+    pub curve: Vec<(u64, u64, u64)>,
+}
+
+/// Plays a single run to the end without a terminal.
+pub fn simulate_run(world: &mut World, policy: &mut dyn Policy, rng: &mut GameRng) -> RunSummary {
+    world.reset();
+    let mut player = Player::default();
+    let mut caves = 0;
+    let mut curve = Vec::new();
+    let mut capped = true;
+
+    while caves < MAX_CAVES {
+        let mut options = engine::generate_caves(world, &player, rng);
+        let clues: Vec<Vec<Clue>> = options
+            .iter()
+            .map(|cave| engine::scout_cave(world, &player, cave, rng))
+            .collect();
+        let choice = policy.choose_cave(&options, &clues, rng);
+        let mut cave = options.remove(choice);
+        match engine::resolve_cave(world, &mut player, &mut cave, "", rng, &mut Silent) {
+            CaveResult::Survived { reward } => {
+                engine::claim_reward(world, &mut player, reward, &mut Silent);
+                caves += 1;
+                if CURVE_POINTS.contains(&caves) {
+                    curve.push((caves, player.gold(), player.xp()));
+                }
+            }
+            CaveResult::Died => {
+                capped = false;
+                break;
+            }
+        }
+    }
+
+    RunSummary {
+        caves,
+        level: player.level(),
+        gold: player.gold(),
+        xp: player.xp(),
+        capped,
+        curve,
+    }
+}
+
+pub struct Report {
+    pub seed: u64,
+    pub runs: Vec<RunSummary>,
+}
+
+/// Plays `runs` runs with `policy`, each seeded from `seed`.
+pub fn simulate(world: &mut World, policy: &mut dyn Policy, runs: u64, seed: u64) -> Report {
+    let mut seeds = GameRng::seed_from_u64(seed);
+    let runs = (0..runs)
+        .map(|_| {
+            let mut rng = GameRng::seed_from_u64(seeds.gen());
+            simulate_run(world, policy, &mut rng)
+        })
+        .collect();
+    Report { seed, runs }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.runs.len().max(1) as f64;
+        let mean = |value: &dyn Fn(&RunSummary) -> u64| {
+            self.runs.iter().map(|run| value(run) as f64).sum::<f64>() / total
+        };
+
+        writeln!(f, "Simulated {} runs (seed {})", self.runs.len(), self.seed)?;
+        writeln!(f, "Mean caves survived: {:.2}", mean(&|run| run.caves))?;
+        writeln!(f, "Mean final gold: {:.2}", mean(&|run| run.gold))?;
+        writeln!(f, "Mean final xp: {:.2}", mean(&|run| run.xp))?;
+
+        let capped = self.runs.iter().filter(|run| run.capped).count();
+        if capped > 0 {
+            writeln!(
+                f,
+                "Runs cut off alive at {} caves: {} ({:.1}%)",
+                MAX_CAVES,
+                capped,
+                capped as f64 / total * 100.0
+            )?;
+        }
+
+        writeln!(f, "\nDeath level distribution:")?;
+        let mut levels: BTreeMap<u64, u64> = BTreeMap::new();
+        for run in self.runs.iter().filter(|run| !run.capped) {
+            *levels.entry(run.level).or_default() += 1;
+        }
+        for (level, count) in levels {
+            writeln!(
+                f,
+                "  level {:>4}: {:>7} ({:>5.1}%)",
+                level,
+                count,
+                count as f64 / total * 100.0
+            )?;
+        }
+
+        writeln!(f, "\nGold/xp curve (means over runs that got that far):")?;
+        for point in CURVE_POINTS.iter() {
+            let samples: Vec<(u64, u64)> = self
+                .runs
+                .iter()
+                .flat_map(|run| run.curve.iter())
+                .filter(|(caves, _, _)| caves == point)
+                .map(|(_, gold, xp)| (*gold, *xp))
+                .collect();
+            if samples.is_empty() {
+                break;
+            }
+            let count = samples.len() as f64;
+            writeln!(
+                f,
+                "  cave {:>4}: {:>7} runs, {:>12.1} gold, {:>12.1} xp",
+                point,
+                samples.len(),
+                samples.iter().map(|(gold, _)| *gold as f64).sum::<f64>() / count,
+                samples.iter().map(|(_, xp)| *xp as f64).sum::<f64>() / count,
+            )?;
+        }
+        Ok(())
+    }
+}